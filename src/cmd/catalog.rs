@@ -0,0 +1,106 @@
+use crate::cmd::code::Code;
+use crate::cmd::error::{Error, Result};
+use serde_json::{Map, Value, json};
+use std::path::Path;
+use std::str::FromStr;
+
+///
+/// 错误码目录输出格式
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeFormat {
+    /// Rust 模块
+    Rust,
+    /// Markdown 表格
+    Markdown,
+    /// JSON 数组
+    Json,
+    /// OpenAPI `components/schemas` 错误码枚举, 扩展名为 .yaml/.yml 时输出 YAML
+    OpenApi,
+    /// TypeScript `const` 映射
+    TypeScript,
+}
+
+impl FromStr for CodeFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "rust" => Ok(CodeFormat::Rust),
+            "md" => Ok(CodeFormat::Markdown),
+            "json" => Ok(CodeFormat::Json),
+            "openapi" => Ok(CodeFormat::OpenApi),
+            "ts" => Ok(CodeFormat::TypeScript),
+            _ => Err(format!("unknown code format: {}", s)),
+        }
+    }
+}
+
+impl CodeFormat {
+    /// 按输出文件扩展名推断格式
+    pub(crate) fn from_path(path: &str) -> CodeFormat {
+        match Path::new(path).extension().and_then(|s| s.to_str()) {
+            Some("md") => CodeFormat::Markdown,
+            Some("json") => CodeFormat::Json,
+            Some("yaml" | "yml") => CodeFormat::OpenApi,
+            Some("ts") => CodeFormat::TypeScript,
+            _ => CodeFormat::Rust,
+        }
+    }
+}
+
+fn entry(row: &Code) -> Value {
+    let mut entry = Map::new();
+    entry.insert("name".into(), json!(row.name));
+    entry.insert("code".into(), json!(row.code));
+    entry.insert("message".into(), json!(row.message));
+    if !row.messages.is_empty() {
+        let messages: Map<String, Value> = row
+            .messages
+            .iter()
+            .map(|m| (m.locale.clone(), json!(m.message)))
+            .collect();
+        entry.insert("messages".into(), Value::Object(messages));
+    }
+    if let Some(http_status) = row.http_status {
+        entry.insert("http_status".into(), json!(http_status));
+    }
+    if let Some(level) = &row.level {
+        entry.insert("level".into(), json!(level));
+    }
+    if let Some(retryable) = row.retryable {
+        entry.insert("retryable".into(), json!(retryable));
+    }
+    Value::Object(entry)
+}
+
+/// JSON 数组, 每个错误码一项
+pub(crate) fn json(rows: &[Code]) -> String {
+    let entries: Vec<Value> = rows.iter().map(entry).collect();
+    serde_json::to_string_pretty(&entries).unwrap_or_default()
+}
+
+/// OpenAPI `components/schemas/ErrorCode` 整数枚举
+pub(crate) fn openapi(rows: &[Code], path: &str) -> Result<String> {
+    let schema = json!({
+        "components": {
+            "schemas": {
+                "ErrorCode": {
+                    "type": "integer",
+                    "enum": rows.iter().map(|r| r.code).collect::<Vec<_>>(),
+                    "x-enum-varnames": rows.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(),
+                    "x-enum-descriptions": rows.iter().map(|r| r.message.as_str()).collect::<Vec<_>>(),
+                }
+            }
+        }
+    });
+    if CodeFormat::from_path(path) == CodeFormat::OpenApi {
+        let content = serde_yml::to_string(&schema).map_err(|source| Error::Yaml {
+            path: path.to_string(),
+            source,
+        })?;
+        Ok(content.trim_end().to_string())
+    } else {
+        Ok(serde_json::to_string_pretty(&schema).unwrap_or_default())
+    }
+}
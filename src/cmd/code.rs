@@ -1,11 +1,15 @@
-use crate::cmd::file;
-use crate::cmd::r#gen::out_file;
+use crate::cmd::catalog::{self, CodeFormat};
+use crate::cmd::config::Config;
+use crate::cmd::error::{Duplicate, Error, Result};
+use crate::cmd::lock::Lock;
+use crate::cmd::plan::{self, OnExists, Plan};
+use crate::cmd::report::{MissingTranslation, Report};
+use crate::cmd::{ident, import, source, stub};
 use clap::{Arg, ArgAction, ArgMatches, Command};
-use minijinja::Environment;
 use regex::Regex;
 use serde::Serialize;
-use std::collections::BTreeMap;
-use std::{fs, path::Path};
+use std::collections::HashMap;
+use std::str::FromStr;
 use walkdir::WalkDir;
 
 // cargo run gen code --out="./errcode/mod.rs" --source="./yaml"
@@ -15,13 +19,23 @@ pub(crate) fn command() -> Command {
         .short_flag('C')
         .long_flag("code")
         .about("code.")
+        .subcommand(import::command())
+        .subcommand_negates_reqs(true)
+        .args_conflicts_with_subcommands(true)
         .arg(
             Arg::new("out")
                 .required(true)
                 .short('o')
                 .long("out")
-                .action(ArgAction::Set)
-                .help("Output file"),
+                .action(ArgAction::Append)
+                .help("Output file, may be repeated"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .action(ArgAction::Append)
+                .value_parser(["rust", "md", "json", "openapi", "ts"])
+                .help("Format of the preceding --out, or of the --out at the same position when all formats come last; inferred from the extension if omitted"),
         )
         .arg(
             Arg::new("source")
@@ -30,123 +44,1152 @@ pub(crate) fn command() -> Command {
                 .long("source")
                 .help("Source Directory"),
         )
+        .arg(
+            Arg::new("lock")
+                .long("lock")
+                .help("Lock file recording assigned codes, e.g. errcode.lock"),
+        )
+        .arg(
+            Arg::new("style")
+                .long("style")
+                .value_parser(["const", "enum"])
+                .help("Output style: ErrorCode constants or an AppError enum"),
+        )
+        .arg(
+            Arg::new("error-code-path")
+                .long("error-code-path")
+                .help("Import path of the ErrorCode type, e.g. hirust_resp::ErrorCode"),
+        )
+        .arg(
+            Arg::new("error-code-constructor")
+                .long("error-code-constructor")
+                .help("ErrorCode constructor with {code} and {message} placeholders"),
+        )
+        .arg(
+            Arg::new("default-locale")
+                .long("default-locale")
+                .help("Locale used for `message` when messages are keyed by locale, e.g. zh-CN"),
+        )
+        .arg(
+            Arg::new("templates")
+                .long("templates")
+                .help("Templates Directory"),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .action(ArgAction::SetTrue)
+                .help("Print a diff instead of writing files"),
+        )
+        .arg(
+            Arg::new("check")
+                .long("check")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("dry-run")
+                .help("Fail with a diff if the generated files are out of date"),
+        )
+        .arg(
+            Arg::new("on-exists")
+                .long("on-exists")
+                .value_parser(["skip", "overwrite", "error", "merge"])
+                .help("Policy for existing output files"),
+        )
 }
 
 #[derive(Serialize)]
 struct CodeStub {
     pub doc: String,
     pub rows: Vec<Code>,
+    // 全部 locale, 按首次出现的顺序
+    pub locales: Vec<String>,
+    pub default_locale: String,
+    // 是否有错误码声明了 http_status/level/retryable
+    pub has_meta: bool,
+    // ErrorCode 导入路径, 为空时生成自包含的 ErrorCode
+    pub error_code_path: String,
+    // get() 中的构造表达式
+    pub get_constructor: String,
 }
 
 #[derive(Serialize)]
-struct Code {
+pub(crate) struct Code {
     pub name: String,
     pub code: i64,
     pub message: String,
+    pub messages: Vec<Message>,
+    pub http_status: Option<u16>,
+    pub level: Option<String>,
+    pub retryable: Option<bool>,
+    // --style=enum 时的变体名与字段
+    pub variant: String,
+    pub fields: Vec<Field>,
+    // ErrorCode 常量的构造表达式
+    pub constructor: String,
+    // 弃用说明, 空字符串表示没有说明
+    pub deprecated: Option<String>,
+    // 定义位置 file:line
+    #[serde(skip)]
+    pub location: String,
 }
 
-#[allow(dead_code)]
-pub(crate) fn execute(arg_matches: &ArgMatches) {
-    let mut code_mod_content = String::from("use resp::ErrorCode;\n");
-    code_mod_content.push("\n".parse().unwrap());
+#[derive(Serialize)]
+pub(crate) struct Field {
+    pub name: String,
+    pub ty: String,
+}
 
-    let out_file = out_file(arg_matches);
+#[derive(Serialize)]
+pub(crate) struct Message {
+    pub locale: String,
+    pub message: String,
+}
+
+#[allow(dead_code)]
+pub(crate) fn execute(arg_matches: &ArgMatches) -> Result<()> {
+    if let Some(import_matches) = arg_matches.subcommand_matches("import") {
+        return import::execute(import_matches);
+    }
     let mut source_dir = String::new();
     if let Some(source) = arg_matches.get_many::<String>("source") {
         source_dir = source.map(|s| s.as_str()).collect::<Vec<_>>().join("");
-        println!("{:?}", source_dir);
-    }
-
-    let mut rows: Vec<Code> = vec![];
-    for entry in WalkDir::new(source_dir) {
-        let entry = entry.unwrap();
-        let file_path = entry.path();
-        let extension = file_path.extension().and_then(|s| s.to_str());
-
-        // 检查扩展名是否为".yaml"
-        if file_path.is_file() && extension == Some("yaml") {
-            let file_name = file_path.file_name().and_then(|s| s.to_str()).unwrap();
-            //println!("{}", file_name);
-            let re = Regex::new(r"\d+").unwrap(); // 编译正则表达式，\d+ 匹配一个或多个数字
-            let file_number = re.captures(file_name).unwrap(); // 提取所有匹配的数字
-
-            //println!("找到的数字: {}", file_number.get(0).map_or("", |m| m.as_str()));
-
-            let mut iota: i64 = file_number
-                .get(0)
-                .map_or("", |m| m.as_str())
-                .parse()
-                .unwrap();
-
-            let yaml =
-                fs::read_to_string(file_path).expect(format!("读取{}失败", file_name).as_str());
-
-            let mut key_sort: Vec<String> = vec![];
-            for row in yaml.as_str().lines() {
-                let re = Regex::new(r"^[a-zA-Z]").unwrap();
-                if re.is_match(row) {
-                    key_sort.push(row.trim().replace(":", ""));
-                }
+    }
+
+    let mut code_gen = CodeGen::new().source(source_dir);
+    for (format, out) in outputs(arg_matches)? {
+        code_gen = match format {
+            CodeFormat::Rust => code_gen.out(out.as_str()),
+            format => code_gen.export(format, out.as_str()),
+        };
+    }
+    code_gen
+        .templates(crate::cmd::r#gen::templates(arg_matches))
+        .lock(crate::cmd::r#gen::lock(arg_matches))
+        .style(arg_string(arg_matches, "style").parse().unwrap_or_default())
+        .error_code_path(arg_string(arg_matches, "error-code-path"))
+        .error_code_constructor(arg_string(arg_matches, "error-code-constructor"))
+        .default_locale(arg_string(arg_matches, "default-locale"))
+        .dry_run(arg_matches.get_flag("dry-run"))
+        .check(arg_matches.get_flag("check"))
+        .on_exists_opt(crate::cmd::r#gen::on_exists(arg_matches))
+        .run()?;
+    Ok(())
+}
+
+// 为每个 --out 确定格式: --format 全部写在最后时按顺序一一对应, 数量必须相同;
+// 否则每个 --format 对应它前面最近的 --out; 未指定时按扩展名推断
+fn outputs(arg_matches: &ArgMatches) -> Result<Vec<(CodeFormat, String)>> {
+    let indexed = |id: &str| -> Vec<(usize, String)> {
+        match (
+            arg_matches.indices_of(id),
+            arg_matches.get_many::<String>(id),
+        ) {
+            (Some(indices), Some(values)) => indices.zip(values.cloned()).collect(),
+            _ => vec![],
+        }
+    };
+    let outs = indexed("out");
+    let formats = indexed("format");
+    let syntax = |message: String| Error::syntax("--format", 0, message);
+
+    let mut paired: Vec<Option<String>> = vec![None; outs.len()];
+    let last_out = outs.last().map_or(0, |(i, _)| *i);
+    if formats.iter().all(|(i, _)| *i > last_out) {
+        if !formats.is_empty() && formats.len() != outs.len() {
+            return Err(syntax(format!(
+                "{} 个 --format 无法与 {} 个 --out 对应, 请在每个 --out 之后写对应的 --format",
+                formats.len(),
+                outs.len()
+            )));
+        }
+        for (slot, (_, format)) in paired.iter_mut().zip(formats) {
+            *slot = Some(format);
+        }
+    } else {
+        for (index, format) in formats {
+            let Some(position) = outs.iter().rposition(|(i, _)| *i < index) else {
+                return Err(syntax(format!("--format={} 之前没有 --out", format)));
+            };
+            if let Some(previous) = &paired[position] {
+                return Err(syntax(format!(
+                    "--out={} 指定了多个 --format: {} 和 {}",
+                    outs[position].1, previous, format
+                )));
+            }
+            paired[position] = Some(format);
+        }
+    }
+
+    outs.into_iter()
+        .zip(paired)
+        .map(|((_, out), format)| {
+            let format = match format {
+                Some(format) => format.parse().map_err(syntax)?,
+                None => CodeFormat::from_path(out.as_str()),
+            };
+            Ok((format, out))
+        })
+        .collect()
+}
+
+///
+/// 错误码输出风格
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CodeStyle {
+    /// `ErrorCode` 常量
+    #[default]
+    Const,
+    /// `AppError` 枚举, 每个错误码一个变体
+    Enum,
+}
+
+impl FromStr for CodeStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "const" => Ok(CodeStyle::Const),
+            "enum" => Ok(CodeStyle::Enum),
+            _ => Err(format!("unknown code style: {}", s)),
+        }
+    }
+}
+
+///
+/// 错误码生成器
+///
+/// Examples
+///```no_run
+/// let report = hirust_gen::CodeGen::new()
+///     .source("./yaml")
+///     .out("./errcode/mod.rs")
+///     .run()?;
+/// # Ok::<(), hirust_gen::Error>(())
+///```
+///
+#[derive(Debug, Clone, Default)]
+pub struct CodeGen {
+    source: String,
+    out: String,
+    exports: Vec<(CodeFormat, String)>,
+    templates: String,
+    lock: String,
+    style: CodeStyle,
+    error_code_path: String,
+    error_code_constructor: String,
+    default_locale: String,
+    dry_run: bool,
+    check: bool,
+    on_exists: Option<OnExists>,
+}
+
+impl CodeGen {
+    pub fn new() -> Self {
+        CodeGen::default()
+    }
+
+    /// 错误码源目录, 支持 .yaml/.yml/.json/.toml
+    pub fn source(mut self, source: impl Into<String>) -> Self {
+        self.source = source.into();
+        self
+    }
+
+    /// 输出文件
+    pub fn out(mut self, out: impl Into<String>) -> Self {
+        self.out = out.into();
+        self
+    }
+
+    /// 额外输出的错误码目录, 可多次调用
+    pub fn export(mut self, format: CodeFormat, out: impl Into<String>) -> Self {
+        match format {
+            CodeFormat::Rust => self.out = out.into(),
+            format => self.exports.push((format, out.into())),
+        }
+        self
+    }
+
+    /// 自定义模板目录
+    pub fn templates(mut self, templates: impl Into<String>) -> Self {
+        self.templates = templates.into();
+        self
+    }
+
+    /// 错误码锁文件, 为空时不使用锁文件
+    pub fn lock(mut self, lock: impl Into<String>) -> Self {
+        self.lock = lock.into();
+        self
+    }
+
+    /// 输出风格, 默认 `CodeStyle::Const`
+    pub fn style(mut self, style: CodeStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// ErrorCode 导入路径, 为空时读取配置文件 `error_code.path`, 都没有时生成自包含的 `ErrorCode`
+    ///
+    /// 类型需要有 `code` 字段和 `message()` 方法
+    pub fn error_code_path(mut self, error_code_path: impl Into<String>) -> Self {
+        self.error_code_path = error_code_path.into();
+        self
+    }
+
+    /// ErrorCode 构造表达式, `{code}`/`{message}` 替换为错误码和 message 表达式,
+    /// 默认 `ErrorCode { code: {code}, message: {message} }`
+    pub fn error_code_constructor(mut self, error_code_constructor: impl Into<String>) -> Self {
+        self.error_code_constructor = error_code_constructor.into();
+        self
+    }
+
+    /// message 按 locale 区分时 `message` 使用的 locale, 为空时取每个错误码的第一个 locale
+    pub fn default_locale(mut self, default_locale: impl Into<String>) -> Self {
+        self.default_locale = default_locale.into();
+        self
+    }
+
+    /// 只输出 diff, 不写文件
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// 检查已有文件是否与生成结果一致, 不一致时返回 [`Error::Stale`](crate::Error::Stale), 不写文件
+    pub fn check(mut self, check: bool) -> Self {
+        self.check = check;
+        self
+    }
+
+    /// 输出文件已存在时的处理策略, 默认 `OnExists::Overwrite`
+    pub fn on_exists(mut self, on_exists: OnExists) -> Self {
+        self.on_exists = Some(on_exists);
+        self
+    }
+
+    fn on_exists_opt(mut self, on_exists: Option<OnExists>) -> Self {
+        self.on_exists = on_exists;
+        self
+    }
+
+    // --check 时按已有文件是否带标记比较, 见 plan::check_policy
+    fn policy(&self, existing: Option<&str>) -> OnExists {
+        let on_exists = self.on_exists.unwrap_or(OnExists::Overwrite);
+        if self.check {
+            plan::check_policy(on_exists, existing)
+        } else {
+            on_exists
+        }
+    }
+
+    pub fn run(&self) -> Result<Report> {
+        let out_file = self.out.clone();
+        let templates = self.templates.clone();
+        let source_dir = self.source.clone();
+
+        let config = Config::load()?;
+        let error_code_path = if self.error_code_path.is_empty() {
+            config.error_code.path.unwrap_or_default()
+        } else {
+            self.error_code_path.clone()
+        };
+        let constructor = if self.error_code_constructor.is_empty() {
+            config
+                .error_code
+                .constructor
+                .unwrap_or_else(|| DEFAULT_CONSTRUCTOR.to_string())
+        } else {
+            self.error_code_constructor.clone()
+        };
+
+        let mut plan = Plan::default();
+        let mut lock = if self.lock.is_empty() {
+            None
+        } else {
+            Some(Lock::load(&plan, self.lock.as_str())?)
+        };
+
+        let number_re = Regex::new(r"\d+").unwrap(); // 编译正则表达式，\d+ 匹配一个或多个数字
+
+        let mut rows: Vec<Code> = vec![];
+        let mut ranges: Vec<Range> = vec![];
+        // 已停用的错误码及声明位置
+        let mut retired: Vec<(i64, String)> = vec![];
+        let mut documents = vec![];
+        for entry in WalkDir::new(source_dir.as_str()).sort_by_file_name() {
+            let entry = entry.map_err(|e| {
+                let path = e
+                    .path()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_else(|| source_dir.clone());
+                Error::io(path.as_str(), e.into())
+            })?;
+            let file_path = entry.path();
+            if !file_path.is_file() {
+                continue;
             }
-            let deserialized_map: BTreeMap<String, serde_yml::Value> =
-                serde_yml::from_str(&yaml).unwrap();
-            for key in key_sort {
-                let value = deserialized_map.get(key.as_str());
-                println!("{} k: {:?}, v: {:?}", iota, key, value);
-                let mut message = key.clone().as_str().to_owned() + "错误";
-                match value {
-                    Some(mapping) => match mapping.as_mapping() {
-                        Some(mapping) => {
-                            if mapping.contains_key("message") {
-                                message = mapping
-                                    .get("message")
-                                    .unwrap()
-                                    .as_str()
-                                    .unwrap()
-                                    .parse()
-                                    .unwrap();
-                            }
-                            if mapping.contains_key("code") {
-                                match mapping.get("code").unwrap().as_i64() {
-                                    Some(code) => {
-                                        iota = code;
-                                        println!("{:?}", iota);
-                                    }
-                                    None => {}
-                                }
-                            }
+            let path = file_path.display().to_string();
+            let Some(document) = source::read(path.as_str())? else {
+                eprintln!("warning: 跳过{}: 不支持的文件类型", path);
+                continue;
+            };
+            documents.push((file_path.to_path_buf(), document));
+        }
+        // 所有源文件中停用的错误码, 自动编号时跳过
+        let retired_codes: Vec<i64> = documents
+            .iter()
+            .flat_map(|(_, document)| document.meta.retired.iter().copied())
+            .collect();
+        for (file_path, document) in documents {
+            let file_path = file_path.as_path();
+            let path = file_path.display().to_string();
+            let file_name = file_path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default();
+            let meta = document.meta;
+            // _meta.base 优先, 未声明时提取文件名中的数字作为起始错误码
+            let base = match meta.base {
+                Some(base) => base,
+                None => number_re
+                    .find(file_name)
+                    .and_then(|m| m.as_str().parse().ok())
+                    .ok_or_else(|| {
+                        Error::syntax(
+                            path.as_str(),
+                            0,
+                            "缺少 _meta.base, 文件名中也没有起始错误码数字",
+                        )
+                    })?,
+            };
+            let module = meta.module.clone().unwrap_or_else(|| {
+                file_path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or_default()
+                    .to_string()
+            });
+            let mut iota = base;
+            let mut end = meta.max.unwrap_or(base);
+
+            for entry in document.entries {
+                let source::Entry {
+                    key,
+                    name,
+                    code,
+                    mut message,
+                    messages,
+                    http_status,
+                    level,
+                    retryable,
+                    fields,
+                    deprecated,
+                    line,
+                } = entry;
+                if let Some((_, text)) = messages
+                    .iter()
+                    .find(|(locale, _)| *locale == self.default_locale)
+                    .or(messages.first())
+                {
+                    message = text.clone();
+                }
+                // 显式code > 锁文件 > 未被占用的下一个错误码
+                let code = match (code, &lock) {
+                    (Some(code), _) => code,
+                    (None, Some(lock)) => lock.code(name.as_str()).unwrap_or_else(|| {
+                        while lock.is_taken(iota, name.as_str())
+                            || rows.iter().any(|r| r.code == iota)
+                            || retired_codes.contains(&iota)
+                        {
+                            iota += 1;
+                        }
+                        iota
+                    }),
+                    (None, None) => {
+                        while retired_codes.contains(&iota) {
+                            iota += 1;
                         }
-                        None => {}
-                    },
-                    None => {}
+                        iota
+                    }
+                };
+                // 声明了 _meta 时错误码必须在模块范围内
+                if meta.line > 0 && (code < base || meta.max.is_some_and(|max| code > max)) {
+                    return Err(Error::syntax(
+                        path.as_str(),
+                        line,
+                        format!(
+                            "{} 的错误码 {} 超出模块 {} 的范围 {}",
+                            key,
+                            code,
+                            module,
+                            range_text(base, meta.max)
+                        ),
+                    ));
                 }
-
+                end = end.max(code);
+                iota = code;
+                // 变体名只在 --style=enum 时使用
+                let variant = match self.style {
+                    CodeStyle::Const => String::new(),
+                    CodeStyle::Enum => ident::variant(name.as_str())
+                        .map_err(|message| Error::syntax(path.as_str(), line, message))?,
+                };
+                let row_constructor = construct(
+                    constructor.as_str(),
+                    code.to_string().as_str(),
+                    format!("{:?}", message).as_str(),
+                );
                 rows.push(Code {
-                    name: key.to_uppercase(),
+                    name,
                     code: iota,
                     message,
+                    messages: messages
+                        .into_iter()
+                        .map(|(locale, message)| Message { locale, message })
+                        .collect(),
+                    http_status,
+                    level,
+                    retryable,
+                    variant,
+                    constructor: row_constructor,
+                    deprecated,
+                    fields: fields
+                        .into_iter()
+                        .map(|(name, ty)| Field { name, ty })
+                        .collect(),
+                    location: format!("{}:{}", path, line),
                 });
                 iota += 1;
             }
+            for code in &meta.retired {
+                retired.push((*code, format!("{}:{}", path, meta.line)));
+            }
+            if meta.line > 0 {
+                let range = Range {
+                    module,
+                    location: format!("{}:{}", path, meta.line),
+                    start: base,
+                    end,
+                };
+                if let Some(other) = ranges.iter().find(|r| r.overlaps(&range)) {
+                    return Err(Error::syntax(
+                        path.as_str(),
+                        meta.line,
+                        format!(
+                            "模块 {} 的错误码范围 {}-{} 与模块 {} ({}) 的范围 {}-{} 重叠",
+                            range.module,
+                            range.start,
+                            range.end,
+                            other.module,
+                            other.location,
+                            other.start,
+                            other.end
+                        ),
+                    ));
+                }
+                ranges.push(range);
+            }
+        }
+
+        check_duplicates(&rows)?;
+        check_retired(&retired, &rows)?;
+        if self.style == CodeStyle::Enum {
+            check_fields(&rows)?;
+        }
+        if let Some(lock) = lock.as_mut() {
+            check_reserved(lock, &rows)?;
+            let assigned: Vec<(String, i64)> =
+                rows.iter().map(|r| (r.name.clone(), r.code)).collect();
+            for (name, code) in lock.update(&assigned) {
+                eprintln!("{} ({}) 已删除, 错误码保留在 {}", name, code, self.lock);
+            }
+            lock.write(&mut plan)?;
+        }
+
+        let (locales, missing_translations) = translations(&rows, self.default_locale.as_str());
+        for missing in &missing_translations {
+            eprintln!("warning: {} 缺少 {} 翻译", missing.name, missing.locale);
+        }
+
+        let has_meta = rows
+            .iter()
+            .any(|r| r.http_status.is_some() || r.level.is_some() || r.retryable.is_some());
+        let doc = String::from("错误码");
+        let stub = CodeStub {
+            doc,
+            rows,
+            locales,
+            default_locale: self.default_locale.clone(),
+            has_meta,
+            error_code_path,
+            get_constructor: construct(constructor.as_str(), "code", "message"),
+        };
+
+        let registry = stub::Registry::load(templates.as_str())?;
+        let mut removed = vec![];
+        if !out_file.is_empty() {
+            // 与上次生成结果比较, 报告删除的错误码
+            let previous = plan.read(out_file.as_str())?;
+            if let Some(previous) = &previous {
+                for (name, code) in removed_codes(previous, &stub.rows, self.style) {
+                    // _meta.retired 或锁文件 reserved 中的错误码不会再分配
+                    let reserved = retired_codes.contains(&code)
+                        || lock
+                            .as_ref()
+                            .and_then(|lock| lock.owner(code))
+                            .is_some_and(|owner| !stub.rows.iter().any(|r| r.name == owner));
+                    if !reserved {
+                        eprintln!(
+                            "warning: {} ({}) 已从生成结果中删除, 请将其错误码加入 _meta.retired",
+                            name, code
+                        );
+                    }
+                    removed.push(name);
+                }
+            }
+
+            let template = match self.style {
+                CodeStyle::Const => stub::CODE,
+                CodeStyle::Enum => stub::CODE_ENUM,
+            };
+            let mod_content = registry.render(template, &stub)?;
+            // 写文件
+            let policy = self.policy(previous.as_deref());
+            plan.write_with(out_file.as_str(), mod_content.as_str(), policy)?;
+        }
+        // 目录文件没有手写部分, merge 和 --check 时按覆盖处理
+        let policy = match self.on_exists.unwrap_or(OnExists::Overwrite) {
+            OnExists::Skip | OnExists::Error if self.check => OnExists::Overwrite,
+            OnExists::Merge => OnExists::Overwrite,
+            policy => policy,
+        };
+        for (format, out) in &self.exports {
+            let content = match format {
+                CodeFormat::Rust => continue,
+                CodeFormat::Markdown => registry.render(stub::CODE_MD, &stub)?,
+                CodeFormat::TypeScript => registry.render(stub::CODE_TS, &stub)?,
+                CodeFormat::Json => catalog::json(&stub.rows),
+                CodeFormat::OpenApi => catalog::openapi(&stub.rows, out.as_str())?,
+            };
+            plan.write_with(out.as_str(), content.as_str(), policy)?;
+        }
+        let mut report = plan.commit(self.dry_run, self.check)?;
+        report.missing_translations = missing_translations;
+        report.removed = removed;
+        Ok(report)
+    }
+}
+
+// 已停用的错误码不能再使用
+fn check_retired(retired: &[(i64, String)], rows: &[Code]) -> Result<()> {
+    let duplicates: Vec<Duplicate> = rows
+        .iter()
+        .filter_map(|row| {
+            let (code, location) = retired.iter().find(|(code, _)| *code == row.code)?;
+            Some(Duplicate {
+                what: format!("错误码 {}", code),
+                first: format!("已停用 ({})", location),
+                second: format!("{} ({})", row.name, row.location),
+            })
+        })
+        .collect();
+    if duplicates.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::DuplicateCode { duplicates })
+    }
+}
+
+// 上次生成结果中存在而本次没有的常量名 (enum 风格为变体名) 及其错误码, 无法解析时不比较
+fn removed_codes(previous: &str, rows: &[Code], style: CodeStyle) -> Vec<(String, i64)> {
+    let Ok(syntax) = syn::parse_file(previous) else {
+        return vec![];
+    };
+    let codes: Vec<(String, i64)> = match style {
+        CodeStyle::Const => match import::constants("", &syntax) {
+            Ok((constants, _)) => constants.into_iter().map(|c| (c.name, c.code)).collect(),
+            Err(_) => return vec![],
+        },
+        CodeStyle::Enum => variant_codes(&syntax),
+    };
+    codes
+        .into_iter()
+        .filter(|(name, _)| {
+            !rows.iter().any(|r| match style {
+                CodeStyle::Const => r.name == *name,
+                CodeStyle::Enum => r.variant == *name,
+            })
+        })
+        .collect()
+}
+
+// AppError::code() 中每个变体的错误码
+fn variant_codes(syntax: &syn::File) -> Vec<(String, i64)> {
+    let code = syntax.items.iter().find_map(|item| match item {
+        syn::Item::Impl(item)
+            if item.trait_.is_none()
+                && matches!(&*item.self_ty, syn::Type::Path(ty) if ty.path.is_ident("AppError")) =>
+        {
+            item.items.iter().find_map(|item| match item {
+                syn::ImplItem::Fn(function) if function.sig.ident == "code" => Some(function),
+                _ => None,
+            })
+        }
+        _ => None,
+    });
+    let Some(syn::Stmt::Expr(syn::Expr::Match(expr), None)) =
+        code.and_then(|code| code.block.stmts.last())
+    else {
+        return vec![];
+    };
+    expr.arms
+        .iter()
+        .filter_map(|arm| {
+            let path = match &arm.pat {
+                syn::Pat::Path(pat) => &pat.path,
+                syn::Pat::Struct(pat) => &pat.path,
+                _ => return None,
+            };
+            let variant = path.segments.last()?.ident.to_string();
+            Some((variant, import::int(&arm.body)?))
+        })
+        .collect()
+}
+
+// 默认按字段构造 ErrorCode
+const DEFAULT_CONSTRUCTOR: &str = "ErrorCode { code: {code}, message: {message} }";
+
+// 替换构造表达式中的占位符, 字段名与变量名相同时使用简写
+fn construct(constructor: &str, code: &str, message: &str) -> String {
+    let mut expr = constructor.to_string();
+    for (field, value) in [("code", code), ("message", message)] {
+        let placeholder = format!("{{{}}}", field);
+        if field == value {
+            expr = expr.replace(format!("{}: {}", field, placeholder).as_str(), field);
+        }
+        expr = expr.replace(placeholder.as_str(), value);
+    }
+    expr
+}
+
+fn arg_string(arg_matches: &ArgMatches, id: &str) -> String {
+    arg_matches
+        .get_one::<String>(id)
+        .cloned()
+        .unwrap_or_default()
+}
+
+// 源文件 _meta 声明的错误码范围, 未声明 max 时到文件内最大的错误码为止
+struct Range {
+    module: String,
+    location: String,
+    start: i64,
+    end: i64,
+}
+
+impl Range {
+    fn overlaps(&self, other: &Range) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+}
+
+fn range_text(base: i64, max: Option<i64>) -> String {
+    match max {
+        Some(max) => format!("{}-{}", base, max),
+        None => format!(">={}", base),
+    }
+}
+
+// 收集全部 locale 及缺少翻译的错误码, 只有字符串 message 的按默认 locale 计
+fn translations(rows: &[Code], default_locale: &str) -> (Vec<String>, Vec<MissingTranslation>) {
+    let mut locales: Vec<String> = vec![];
+    for message in rows.iter().flat_map(|row| row.messages.iter()) {
+        if !locales.contains(&message.locale) {
+            locales.push(message.locale.clone());
+        }
+    }
+    let mut missing = vec![];
+    for row in rows {
+        for locale in &locales {
+            let translated = if row.messages.is_empty() {
+                locale == default_locale
+            } else {
+                row.messages.iter().any(|m| m.locale == *locale)
+            };
+            if !translated {
+                missing.push(MissingTranslation {
+                    name: row.name.clone(),
+                    locale: locale.clone(),
+                });
+            }
+        }
+    }
+    (locales, missing)
+}
+
+// message 中的 {field} 必须是声明过的字段
+fn check_fields(rows: &[Code]) -> Result<()> {
+    let placeholder_re = Regex::new(r"\{(\w+)\}").unwrap();
+    for row in rows {
+        // 所有 locale 的 message 都会插值, {{ 和 }} 是转义的花括号
+        let messages = std::iter::once(row.message.as_str())
+            .chain(row.messages.iter().map(|m| m.message.as_str()))
+            .map(|message| message.replace("{{", "").replace("}}", ""))
+            .collect::<Vec<_>>()
+            .join("\n");
+        for captures in placeholder_re.captures_iter(messages.as_str()) {
+            let field = &captures[1];
+            if !row.fields.iter().any(|f| f.name == field) {
+                let (path, line) = row.location.rsplit_once(':').unwrap_or_default();
+                return Err(Error::syntax(
+                    path,
+                    line.parse().unwrap_or(0),
+                    format!("{} 的 message 引用了未声明的字段 {}", row.name, field),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+// 检查整个源目录中重复的错误码和常量名
+fn check_duplicates(rows: &[Code]) -> Result<()> {
+    let mut duplicates: Vec<Duplicate> = vec![];
+    let mut codes: HashMap<i64, &Code> = HashMap::new();
+    let mut names: HashMap<&str, &Code> = HashMap::new();
+    for row in rows {
+        if let Some(first) = codes.get(&row.code) {
+            duplicates.push(Duplicate {
+                what: format!("错误码 {}", row.code),
+                first: format!("{} ({})", first.name, first.location),
+                second: format!("{} ({})", row.name, row.location),
+            });
+        } else {
+            codes.insert(row.code, row);
+        }
+        if let Some(first) = names.get(row.name.as_str()) {
+            duplicates.push(Duplicate {
+                what: format!("名称 {}", row.name),
+                first: format!("{} ({})", first.code, first.location),
+                second: format!("{} ({})", row.code, row.location),
+            });
+        } else {
+            names.insert(row.name.as_str(), row);
+        }
+    }
+    if duplicates.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::DuplicateCode { duplicates })
+    }
+}
+
+// 锁文件中已删除名称的错误码不能再分配给其他名称
+fn check_reserved(lock: &Lock, rows: &[Code]) -> Result<()> {
+    let duplicates: Vec<Duplicate> = rows
+        .iter()
+        .filter_map(|row| {
+            let owner = lock.owner(row.code)?;
+            if owner == row.name || rows.iter().any(|r| r.name == owner) {
+                return None;
+            }
+            Some(Duplicate {
+                what: format!("错误码 {}", row.code),
+                first: format!("{} (已删除, 保留在锁文件中)", owner),
+                second: format!("{} ({})", row.name, row.location),
+            })
+        })
+        .collect();
+    if duplicates.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::DuplicateCode { duplicates })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::file::scratch;
+
+    // 在 dir/yaml 下写入源文件, 返回生成器
+    fn sources(dir: &str, files: &[(&str, &str)]) -> CodeGen {
+        std::fs::create_dir_all(format!("{}/yaml", dir)).unwrap();
+        for (name, content) in files {
+            std::fs::write(format!("{}/yaml/{}", dir, name), content).unwrap();
         }
+        CodeGen::new()
+            .source(format!("{}/yaml", dir))
+            .out(format!("{}/errcode.rs", dir))
+    }
+
+    // 生成结果中的 (常量名, 错误码)
+    fn codes(dir: &str) -> Vec<(String, i64)> {
+        let content = std::fs::read_to_string(format!("{}/errcode.rs", dir)).unwrap();
+        let syntax = syn::parse_file(content.as_str()).unwrap();
+        let (constants, _) = import::constants("errcode.rs", &syntax).unwrap();
+        constants.into_iter().map(|c| (c.name, c.code)).collect()
+    }
+
+    #[test]
+    fn skips_codes_retired_in_other_files() {
+        let dir = scratch("code-retired");
+        sources(
+            dir.as_str(),
+            &[
+                (
+                    "a.yaml",
+                    "_meta: {base: 100, max: 199, retired: [200]}\na: A\n",
+                ),
+                ("b.yaml", "_meta: {base: 200, max: 299}\nb: B\nc: C\n"),
+            ],
+        )
+        .run()
+        .unwrap();
+        assert_eq!(
+            codes(dir.as_str()),
+            [
+                (String::from("A"), 100),
+                (String::from("B"), 201),
+                (String::from("C"), 202)
+            ]
+        );
     }
 
-    let doc = String::from("错误码");
-    let stub = CodeStub { doc, rows };
+    #[test]
+    fn lock_keeps_codes_when_inserting_in_the_middle() {
+        let dir = scratch("lock-insert");
+        let lock = format!("{}/errcode.lock", dir);
+        sources(
+            dir.as_str(),
+            &[("user.yaml", "_meta: {base: 100}\na: A\nc: C\n")],
+        )
+        .lock(lock.as_str())
+        .run()
+        .unwrap();
+        sources(
+            dir.as_str(),
+            &[("user.yaml", "_meta: {base: 100}\na: A\nb: B\nc: C\n")],
+        )
+        .lock(lock.as_str())
+        .run()
+        .unwrap();
+        assert_eq!(
+            codes(dir.as_str()),
+            [
+                (String::from("A"), 100),
+                (String::from("B"), 102),
+                (String::from("C"), 101)
+            ]
+        );
+    }
+
+    #[test]
+    fn lock_reserves_removed_names() {
+        let dir = scratch("lock-reserved");
+        let lock = format!("{}/errcode.lock", dir);
+        let run = |source: &str| {
+            sources(dir.as_str(), &[("user.yaml", source)])
+                .lock(lock.as_str())
+                .run()
+                .unwrap()
+        };
+        run("_meta: {base: 100}\na: A\nb: B\n");
+        run("_meta: {base: 100}\na: A\n");
+        assert_eq!(
+            std::fs::read_to_string(&lock).unwrap(),
+            "codes:\n  A: 100\nreserved:\n  B: 101\n"
+        );
+        // 新名称不会分配保留的错误码, 恢复的名称沿用原错误码
+        run("_meta: {base: 100}\na: A\nc: C\n");
+        assert_eq!(
+            codes(dir.as_str()),
+            [(String::from("A"), 100), (String::from("C"), 102)]
+        );
+        run("_meta: {base: 100}\na: A\nc: C\nb: B\n");
+        assert_eq!(
+            codes(dir.as_str()),
+            [
+                (String::from("A"), 100),
+                (String::from("C"), 102),
+                (String::from("B"), 101)
+            ]
+        );
+    }
+
+    #[test]
+    fn lock_unchanged_under_dry_run_and_check() {
+        let dir = scratch("lock-dry-run");
+        let lock = format!("{}/errcode.lock", dir);
+        sources(dir.as_str(), &[("user.yaml", "_meta: {base: 100}\na: A\n")])
+            .lock(lock.as_str())
+            .run()
+            .unwrap();
+        let before = std::fs::read_to_string(&lock).unwrap();
+        let changed = sources(dir.as_str(), &[("user.yaml", "_meta: {base: 100}\nb: B\n")])
+            .lock(lock.as_str());
+        changed.clone().dry_run(true).run().unwrap();
+        assert_eq!(std::fs::read_to_string(&lock).unwrap(), before);
+        assert!(matches!(
+            changed.check(true).run(),
+            Err(Error::Stale { .. })
+        ));
+        assert_eq!(std::fs::read_to_string(&lock).unwrap(), before);
+        assert_eq!(codes(dir.as_str()), [(String::from("A"), 100)]);
+    }
+
+    // gen code 的参数对应的 (格式, 输出文件)
+    fn parsed(args: &[&str]) -> Result<Vec<(CodeFormat, String)>> {
+        let args = ["code", "--source=./yaml"].iter().chain(args);
+        outputs(&command().try_get_matches_from(args).unwrap())
+    }
+
+    #[test]
+    fn format_follows_its_out() {
+        assert_eq!(
+            parsed(&[
+                "-o",
+                "errcode.rs",
+                "-o",
+                "codes.txt",
+                "--format=md",
+                "-o",
+                "api.json"
+            ])
+            .unwrap(),
+            [
+                (CodeFormat::Rust, String::from("errcode.rs")),
+                (CodeFormat::Markdown, String::from("codes.txt")),
+                (CodeFormat::Json, String::from("api.json")),
+            ]
+        );
+    }
+
+    #[test]
+    fn trailing_formats_pair_in_order() {
+        assert_eq!(
+            parsed(&[
+                "-o",
+                "a.txt",
+                "-o",
+                "b.txt",
+                "--format=ts",
+                "--format=openapi"
+            ])
+            .unwrap(),
+            [
+                (CodeFormat::TypeScript, String::from("a.txt")),
+                (CodeFormat::OpenApi, String::from("b.txt")),
+            ]
+        );
+    }
+
+    #[test]
+    fn format_defaults_to_extension() {
+        assert_eq!(
+            parsed(&["-o", "errcode.rs", "-o", "errors.yaml", "-o", "codes.md"]).unwrap(),
+            [
+                (CodeFormat::Rust, String::from("errcode.rs")),
+                (CodeFormat::OpenApi, String::from("errors.yaml")),
+                (CodeFormat::Markdown, String::from("codes.md")),
+            ]
+        );
+    }
+
+    #[test]
+    fn more_formats_than_outs() {
+        let err = parsed(&[
+            "-o",
+            "x.rs",
+            "-o",
+            "api.json",
+            "--format=rust",
+            "--format=openapi",
+            "--format=md",
+        ])
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "--format: 3 个 --format 无法与 2 个 --out 对应, 请在每个 --out 之后写对应的 --format"
+        );
+        let err = parsed(&["-o", "x.rs", "-o", "api.json", "--format=openapi"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "--format: 1 个 --format 无法与 2 个 --out 对应, 请在每个 --out 之后写对应的 --format"
+        );
+    }
+
+    #[test]
+    fn mismatched_formats() {
+        let err = parsed(&["--format=md", "-o", "a.txt", "-o", "b.txt"]).unwrap_err();
+        assert_eq!(err.to_string(), "--format: --format=md 之前没有 --out");
+        let err =
+            parsed(&["-o", "a.txt", "--format=md", "--format=ts", "-o", "b.txt"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "--format: --out=a.txt 指定了多个 --format: md 和 ts"
+        );
+    }
 
-    let binding = file!();
-    let path = Path::new(&binding);
-    let stub_path = format!("{}", path.parent().unwrap().display());
+    // 源文件的生成错误, 路径替换为 yaml/<文件名>
+    fn failure(name: &str, files: &[(&str, &str)]) -> String {
+        let dir = scratch(name);
+        let err = sources(dir.as_str(), files).run().unwrap_err();
+        err.to_string().replace(dir.as_str(), "")
+    }
 
-    let stub_template = fs::read_to_string(stub_path + "/../stubs/code.stub").unwrap();
+    #[test]
+    fn code_outside_module_range() {
+        assert_eq!(
+            failure(
+                "code-overflow",
+                &[(
+                    "user.yaml",
+                    "_meta: {base: 100, max: 101}\na: A\nb: B\nc: C\n"
+                )]
+            ),
+            "/yaml/user.yaml:4: c 的错误码 102 超出模块 user 的范围 100-101"
+        );
+    }
 
-    let mut env = Environment::new();
-    env.add_template("stub", stub_template.as_str()).unwrap();
-    let template = env.get_template("stub").unwrap();
+    #[test]
+    fn overlapping_module_ranges() {
+        assert_eq!(
+            failure(
+                "code-overlap",
+                &[
+                    ("order.yaml", "_meta: {base: 100, max: 199}\na: A\n"),
+                    ("user.yaml", "_meta: {base: 150, max: 250}\nb: B\n"),
+                ]
+            ),
+            "/yaml/user.yaml:1: 模块 user 的错误码范围 150-250 与模块 order (/yaml/order.yaml:1) 的范围 100-199 重叠"
+        );
+    }
 
-    let mod_content = template.render(stub).unwrap();
+    #[test]
+    fn duplicate_names() {
+        assert_eq!(
+            failure(
+                "code-dup-name",
+                &[
+                    (
+                        "order.yaml",
+                        "_meta: {base: 100, max: 199}\nuser:\n  a: A\n"
+                    ),
+                    ("user.yaml", "_meta: {base: 200, max: 299}\nuser_a: A\n"),
+                ]
+            ),
+            "发现1处重复定义:\n  名称 USER_A: 100 (/yaml/order.yaml:3) 与 200 (/yaml/user.yaml:2)"
+        );
+    }
 
-    println!("{}", mod_content);
-    // 写文件
-    file::write_file(out_file.as_str(), mod_content.as_str());
+    #[test]
+    fn duplicate_codes() {
+        assert_eq!(
+            failure(
+                "code-dup-code",
+                &[("user_100.yaml", "a: A\nb: {code: 100, message: B}\n")]
+            ),
+            "发现1处重复定义:\n  错误码 100: A (/yaml/user_100.yaml:1) 与 B (/yaml/user_100.yaml:2)"
+        );
+    }
 }
@@ -0,0 +1,57 @@
+use crate::cmd::error::{Error, Result};
+use serde::Deserialize;
+use std::fs;
+use std::io::ErrorKind;
+
+// 项目配置文件, 位于执行目录下
+pub(crate) const CONFIG_FILE: &str = "hirust-gen.yaml";
+
+///
+/// 项目配置
+///
+/// Examples
+///```yaml
+/// templates: ./stubs
+/// error_code:
+///   path: hirust_resp::ErrorCode
+///   constructor: "ErrorCode::new({code}, {message})"
+///```
+///
+#[derive(Deserialize, Default, Debug)]
+pub(crate) struct Config {
+    // 自定义模板目录
+    #[serde(default)]
+    pub templates: Option<String>,
+    // gen code 使用的 ErrorCode 类型
+    #[serde(default)]
+    pub error_code: ErrorCodeConfig,
+}
+
+///
+/// 错误码类型配置, 未配置 `path` 时生成自包含的 `ErrorCode` 结构体
+///
+#[derive(Deserialize, Default, Debug)]
+pub(crate) struct ErrorCodeConfig {
+    // 导入路径, 如 hirust_resp::ErrorCode
+    #[serde(default)]
+    pub path: Option<String>,
+    // 构造表达式, {code}/{message} 替换为错误码和 message
+    #[serde(default)]
+    pub constructor: Option<String>,
+}
+
+impl Config {
+    #[allow(dead_code)]
+    pub(crate) fn load() -> Result<Config> {
+        match fs::read_to_string(CONFIG_FILE) {
+            Ok(content) if content.trim().is_empty() => Ok(Config::default()),
+            Ok(content) => serde_yml::from_str(&content).map_err(|source| Error::Yaml {
+                path: CONFIG_FILE.to_string(),
+                source,
+            }),
+            // 只有配置文件不存在时使用默认配置, 无法读取时报错
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(Error::io(CONFIG_FILE, e)),
+        }
+    }
+}
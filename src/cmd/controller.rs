@@ -1,10 +1,14 @@
-use crate::cmd::file;
+use crate::cmd::error::{Error, Result};
+use crate::cmd::plan::{OnExists, Plan};
+use crate::cmd::report::Report;
+use crate::cmd::stub;
 use clap::{Arg, ArgAction, ArgMatches, Command};
-use minijinja::Environment;
 use quote::quote;
 use serde::Serialize;
 use std::collections::HashMap;
-use std::{fs, path::Path};
+use std::io::Write;
+use std::path::Path;
+use std::process::Stdio;
 use syn::{File, Item, ItemFn, ItemMod, Stmt, parse_file, parse_str};
 
 // cargo run gen controller --out="./src/app/controllers" --name="test"
@@ -31,6 +35,30 @@ pub(crate) fn command() -> Command {
                 .help("name"),
         )
         .arg(Arg::new("tag").short('t').long("tag").help("tag"))
+        .arg(
+            Arg::new("templates")
+                .long("templates")
+                .help("Templates Directory"),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .action(ArgAction::SetTrue)
+                .help("Print a diff instead of writing files"),
+        )
+        .arg(
+            Arg::new("check")
+                .long("check")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("dry-run")
+                .help("Fail with a diff if the generated files are out of date"),
+        )
+        .arg(
+            Arg::new("on-exists")
+                .long("on-exists")
+                .value_parser(["skip", "overwrite", "error", "merge"])
+                .help("Policy for existing output files"),
+        )
 }
 
 #[derive(Serialize, Debug)]
@@ -41,246 +69,359 @@ struct ControllerStub {
 }
 
 #[allow(dead_code)]
-pub(crate) fn execute(arg_matches: &ArgMatches) {
-    let out_file = crate::cmd::r#gen::out_file(arg_matches);
-    let import_mod_file = out_file.clone() + "/mod.rs";
-    let name: String = crate::cmd::r#gen::name(arg_matches);
-    let source_file = out_file.clone() + "/" + name.as_str() + ".rs";
+pub(crate) fn execute(arg_matches: &ArgMatches) -> Result<()> {
+    ControllerGen::new()
+        .out(crate::cmd::r#gen::out_file(arg_matches))
+        .name(crate::cmd::r#gen::name(arg_matches))
+        .tag(crate::cmd::r#gen::tag(arg_matches))
+        .templates(crate::cmd::r#gen::templates(arg_matches))
+        .dry_run(arg_matches.get_flag("dry-run"))
+        .check(arg_matches.get_flag("check"))
+        .on_exists_opt(crate::cmd::r#gen::on_exists(arg_matches))
+        .run()?;
+    Ok(())
+}
 
-    let tag: String = crate::cmd::r#gen::tag(arg_matches);
+///
+/// 控制器生成器, 生成控制器文件并注册到上级 `mod.rs` 的 `configure` 函数
+///
+/// Examples
+///```no_run
+/// let report = hirust_gen::ControllerGen::new()
+///     .out("./src/app/controllers")
+///     .name("test")
+///     .run()?;
+/// # Ok::<(), hirust_gen::Error>(())
+///```
+///
+#[derive(Debug, Clone, Default)]
+pub struct ControllerGen {
+    out: String,
+    name: String,
+    tag: String,
+    templates: String,
+    dry_run: bool,
+    check: bool,
+    on_exists: Option<OnExists>,
+}
 
-    let mut tag_prefix = tag.clone();
+impl ControllerGen {
+    pub fn new() -> Self {
+        ControllerGen::default()
+    }
 
-    if tag_prefix.is_empty() {
-        let binding = out_file.clone();
-        let binding = binding.as_str();
-        let path = Path::new(&binding);
-        tag_prefix = format!("{}::{}", path.file_name().unwrap().display(), name);
+    /// 输出目录
+    pub fn out(mut self, out: impl Into<String>) -> Self {
+        self.out = out.into();
+        self
     }
 
-    let stub = ControllerStub {
-        source_file: source_file.clone(),
-        scope: name.clone(),
-        tag_prefix: tag_prefix.clone(),
-    };
+    /// 控制器名称, 同时作为路由 scope
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
 
-    let binding = file!();
-    let path = Path::new(&binding);
-    let stub_path = format!("{}", path.parent().unwrap().display());
-    let stub_template = fs::read_to_string(stub_path + "/../stubs/controller.stub").unwrap();
-
-    let mut env = Environment::new();
-    env.add_template("stub", stub_template.as_str()).unwrap();
-    let template = env.get_template("stub").unwrap();
-
-    let mod_content = template.render(stub).unwrap();
-
-    let out_file_dir_list: Vec<String> =
-        out_file.clone().split("/").map(|x| x.to_string()).collect();
-    let mut out_file_dir_temp = String::new();
-    for out_file_dir in out_file_dir_list {
-        out_file_dir_temp =
-            String::from(out_file_dir_temp.to_owned() + out_file_dir.as_str() + "/");
-        if !std::path::Path::new(out_file_dir_temp.clone().as_str()).exists() {
-            fs::create_dir(out_file.as_str())
-                .expect(format!("创建{}目录失败", out_file_dir_temp.clone()).as_str());
-        }
+    /// tag 前缀, 默认为 `<输出目录名>::<name>`
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = tag.into();
+        self
     }
 
-    if !std::path::Path::new(source_file.clone().as_str()).exists() {
-        file::create_file(source_file.as_str());
-        // 写文件
-        file::write_file(source_file.as_str(), mod_content.as_str());
-    } else {
-        println!("{}已经存在", source_file.clone());
-        return;
+    /// 自定义模板目录
+    pub fn templates(mut self, templates: impl Into<String>) -> Self {
+        self.templates = templates.into();
+        self
     }
 
-    // mod导入
-    if !std::path::Path::new(import_mod_file.clone().as_str()).exists() {
-        file::create_file(import_mod_file.clone().as_str());
+    /// 只输出 diff, 不写文件
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
     }
-    let content = fs::read_to_string(import_mod_file.clone().as_str())
-        .expect(format!("读取{}文件失败", import_mod_file.clone()).as_str());
 
-    let mut new_mod_content = String::new();
-    let mut is_new_mod_ok = false;
+    /// 检查已有文件是否与生成结果一致, 不一致时返回 [`Error::Stale`](crate::Error::Stale), 不写文件
+    pub fn check(mut self, check: bool) -> Self {
+        self.check = check;
+        self
+    }
 
-    let line_break = String::from("\n");
+    /// 输出文件已存在时的处理策略, 默认 `OnExists::Skip`, `Merge` 与 `Skip` 相同 (控制器文件为手写代码)
+    pub fn on_exists(mut self, on_exists: OnExists) -> Self {
+        self.on_exists = Some(on_exists);
+        self
+    }
 
-    // 解析文件内容为 AST
-    let syntax: File = parse_file(&content).expect("Not valid Rust code");
-    for item in syntax.items {
-        if let Item::Mod(ItemMod { .. }) = item {
-            if !is_new_mod_ok {
-                let item_str = format!("pub mod {};", name.clone());
-                new_mod_content.push_str(item_str.as_str());
-                new_mod_content.push_str(line_break.clone().as_str());
-                let item_str = quote! {#item}.to_string();
-                new_mod_content.push_str(item_str.as_str());
-                is_new_mod_ok = true;
-            } else {
-                let item_str = quote! {#item}.to_string();
-                new_mod_content.push_str(item_str.as_str());
-            }
-        } else if let Item::Fn(ItemFn {
-            ref vis,
-            ref sig,
-            ref attrs,
-            ref block,
-        }) = item
-        {
-            let fn_name = &sig.ident;
-            // 寻找configure函数
-            if fn_name.eq("configure") {
-                // 函数tokens
-                //let fn_sig_str = quote! {#sig}.to_string();
-                // 提取函数参数
-                let args_map = parse_extract_args(quote! {#sig});
-                // 参数名
-                let arg_name = args_map.values().next().unwrap();
-
-                // 抽取函数体语句
-                let statements = block.stmts.clone();
-
-                let mut i = 0;
-                let statements_len = &statements.len();
-
-                let mut new_statements: Vec<Stmt> = vec![];
-
-                // 组装scope
-                for statement in &statements {
-                    new_statements.push(statement.clone());
-                    if i == statements_len - 2 {
-                        // 将字符串转换成Stmt
-                        let stmt = parse_str::<Stmt>(
-                            format!(
-                                "let {} = {}.configure({}::routes);",
-                                arg_name.clone(),
-                                arg_name.clone(),
-                                name.clone()
+    fn on_exists_opt(mut self, on_exists: Option<OnExists>) -> Self {
+        self.on_exists = on_exists;
+        self
+    }
+
+    pub fn run(&self) -> Result<Report> {
+        let out_file = self.out.clone();
+        let templates = self.templates.clone();
+        let import_mod_file = out_file.clone() + "/mod.rs";
+        let name: String = self.name.clone();
+        let source_file = out_file.clone() + "/" + name.as_str() + ".rs";
+
+        let tag: String = self.tag.clone();
+
+        let mut tag_prefix = tag.clone();
+
+        if tag_prefix.is_empty() {
+            let binding = out_file.clone();
+            let binding = binding.as_str();
+            let path = Path::new(&binding);
+            let parent = path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default();
+            tag_prefix = format!("{}::{}", parent, name);
+        }
+
+        let stub = ControllerStub {
+            source_file: source_file.clone(),
+            scope: name.clone(),
+            tag_prefix: tag_prefix.clone(),
+        };
+
+        let mod_content =
+            stub::Registry::load(templates.as_str())?.render(stub::CONTROLLER, stub)?;
+
+        let mut plan = Plan::default();
+
+        // 控制器为手写代码, --check 时只检查mod注册
+        let on_exists = match self.on_exists.unwrap_or(OnExists::Skip) {
+            _ if self.check => OnExists::Skip,
+            OnExists::Merge => OnExists::Skip,
+            on_exists => on_exists,
+        };
+        // 写文件
+        plan.write_with(source_file.as_str(), mod_content.as_str(), on_exists)?;
+
+        // mod导入
+        let content = plan.read(import_mod_file.as_str())?.unwrap_or_default();
+
+        let mut new_mod_content = String::new();
+
+        let line_break = String::from("\n");
+
+        // 解析文件内容为 AST
+        let syntax: File = parse_file(&content).map_err(|source| Error::RustParse {
+            path: import_mod_file.clone(),
+            source,
+        })?;
+        // mod声明和routes分别检查, 只补充缺少的部分
+        let (has_mod, has_routes) = registered(&syntax, name.as_str());
+        if has_mod && has_routes {
+            return plan.commit(self.dry_run, self.check);
+        }
+        if !has_routes && !syntax.items.iter().any(is_configure) {
+            return Err(Error::syntax(
+                import_mod_file.as_str(),
+                0,
+                format!("缺少 configure 函数, 无法注册 {}::routes", name),
+            ));
+        }
+        let mut is_new_mod_ok = has_mod;
+        for item in syntax.items {
+            if let Item::Mod(ItemMod { .. }) = item {
+                if !is_new_mod_ok {
+                    let item_str = format!("pub mod {};", name.clone());
+                    new_mod_content.push_str(item_str.as_str());
+                    new_mod_content.push_str(line_break.clone().as_str());
+                    let item_str = quote! {#item}.to_string();
+                    new_mod_content.push_str(item_str.as_str());
+                    is_new_mod_ok = true;
+                } else {
+                    let item_str = quote! {#item}.to_string();
+                    new_mod_content.push_str(item_str.as_str());
+                }
+            } else if let Item::Fn(ItemFn {
+                ref vis,
+                ref sig,
+                ref attrs,
+                ref block,
+            }) = item
+            {
+                let fn_name = &sig.ident;
+                // 寻找configure函数
+                if fn_name.eq("configure") && !has_routes {
+                    // 函数tokens
+                    //let fn_sig_str = quote! {#sig}.to_string();
+                    // 提取函数参数
+                    let args_map = parse_extract_args(quote! {#sig});
+                    // 参数名
+                    let arg_name = args_map.values().next().ok_or_else(|| {
+                        Error::syntax(import_mod_file.as_str(), 0, "configure 函数缺少参数")
+                    })?;
+
+                    // 抽取函数体语句
+                    let statements = block.stmts.clone();
+
+                    // 插在最后一条语句之前, 语句不足两条时同样适用
+                    let insert_at = statements.len().saturating_sub(1);
+
+                    let mut new_statements: Vec<Stmt> = vec![];
+
+                    // 组装scope
+                    for i in 0..=statements.len() {
+                        if i == insert_at {
+                            // 将字符串转换成Stmt
+                            let stmt = parse_str::<Stmt>(
+                                format!(
+                                    "let {} = {}.configure({}::routes);",
+                                    arg_name.clone(),
+                                    arg_name.clone(),
+                                    name.clone()
+                                )
+                                .as_str(),
                             )
-                            .as_str(),
-                        )
-                        .unwrap();
+                            .map_err(|source| Error::RustParse {
+                                path: import_mod_file.clone(),
+                                source,
+                            })?;
 
-                        new_statements.push(stmt.clone());
+                            new_statements.push(stmt.clone());
+                        }
+                        if let Some(statement) = statements.get(i) {
+                            new_statements.push(statement.clone());
+                        }
                     }
-                    i += 1;
-                }
 
-                // 使用解析输入重构函数，然后输出
-                let new_configure_fn_str = quote! {
-                    // 在该函数上重复其他所有属性（保持不变）
-                    #(#attrs)*
-                    // 重构函数声明
-                    #vis #sig {
-                        #(#new_statements)*
+                    // 使用解析输入重构函数，然后输出
+                    let new_configure_fn_str = quote! {
+                        // 在该函数上重复其他所有属性（保持不变）
+                        #(#attrs)*
+                        // 重构函数声明
+                        #vis #sig {
+                            #(#new_statements)*
+                        }
                     }
+                    .to_string();
+                    new_mod_content.push_str(new_configure_fn_str.as_str());
+                } else {
+                    // 其他函数
+                    let item_str = quote! {#item}.to_string();
+                    new_mod_content.push_str(item_str.as_str());
                 }
-                .to_string();
-                new_mod_content.push_str(new_configure_fn_str.as_str());
             } else {
-                // 其他函数
                 let item_str = quote! {#item}.to_string();
                 new_mod_content.push_str(item_str.as_str());
             }
-        } else {
-            let item_str = quote! {#item}.to_string();
-            new_mod_content.push_str(item_str.as_str());
+            new_mod_content.push_str(line_break.clone().as_str());
         }
-        new_mod_content.push_str(line_break.clone().as_str());
-    }
 
-    //let syntax: File = parse_file(&new_mod_content).expect("Not valid Rust code");
-    //eprintln!("new_syntax_content：{:#?}", quote! {#syntax}.to_string());
-    // 写文件
-    //file::write_file(import_mod_file.clone().as_str(), quote! {#syntax}.to_string().as_str());
-    file::write_file(import_mod_file.clone().as_str(), &new_mod_content.as_str());
-
-    // rustfmt格式化文件
-    let output = std::process::Command::new("rustfmt")
-        .args([import_mod_file.clone().as_str(), "--edition", "2024"])
-        .output() // 执行命令并等待它结束
-        .expect("Failed to execute process");
-    if output.status.success() {
-        // 输出标准输出
-        if let Ok(stdout) = String::from_utf8(output.stdout) {
-            println!("stdout: {}", stdout);
-        } else {
-            eprintln!("Failed to convert stdout to UTF-8");
+        if !is_new_mod_ok {
+            new_mod_content.insert_str(0, format!("pub mod {};\n", name).as_str());
         }
 
-        // 输出标准错误
-        if let Ok(stderr) = String::from_utf8(output.stderr) {
-            println!("stderr: {}", stderr);
-        } else {
-            eprintln!("Failed to convert stderr to UTF-8");
-        }
-    } else {
-        eprintln!("Process did not run successfully");
-        // 输出标准错误
-        if let Ok(stderr) = String::from_utf8(output.stderr) {
-            println!("stderr: {}", stderr);
-        } else {
-            eprintln!("Failed to convert stderr to UTF-8");
+        // rustfmt格式化后写文件
+        let new_mod_content = rustfmt(import_mod_file.as_str(), new_mod_content.as_str())?;
+        plan.write(import_mod_file.as_str(), new_mod_content.trim_end())?;
+        plan.commit(self.dry_run, self.check)
+    }
+}
+
+fn is_configure(item: &Item) -> bool {
+    matches!(item, Item::Fn(item_fn) if item_fn.sig.ident == "configure")
+}
+
+// 是否已有mod声明, configure函数中是否已配置routes
+fn registered(syntax: &File, name: &str) -> (bool, bool) {
+    let has_mod = syntax
+        .items
+        .iter()
+        .any(|item| matches!(item, Item::Mod(item_mod) if item_mod.ident == name));
+    let routes = format!("{} :: routes", name);
+    let has_routes = syntax.items.iter().any(|item| match item {
+        Item::Fn(item_fn) if item_fn.sig.ident == "configure" => {
+            let block = &item_fn.block;
+            quote! {#block}.to_string().contains(routes.as_str())
         }
+        _ => false,
+    });
+    (has_mod, has_routes)
+}
+
+// 通过标准输入调用rustfmt, 返回格式化后的内容
+fn rustfmt(path: &str, content: &str) -> Result<String> {
+    let rustfmt_err = |message: String| Error::Rustfmt {
+        path: path.to_string(),
+        message,
+    };
+    let mut child = std::process::Command::new("rustfmt")
+        .args(["--edition", "2024", "--emit", "stdout"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| rustfmt_err(e.to_string()))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(content.as_bytes())
+            .map_err(|e| rustfmt_err(e.to_string()))?;
+    }
+    let output = child
+        .wait_with_output() // 执行命令并等待它结束
+        .map_err(|e| rustfmt_err(e.to_string()))?;
+    if !output.status.success() {
+        return Err(rustfmt_err(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
     }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
 }
 
 #[allow(unused)]
 pub fn parse_extract_args(tokens: proc_macro2::TokenStream) -> HashMap<String, String> {
     let mut args_map = HashMap::<String, String>::new();
     for token in tokens.into_iter() {
-        match token {
-            // 遍历TokenTree::Group下的TokenStream
-            proc_macro2::TokenTree::Group(ref group) => {
-                let mut key = String::new();
-                let mut value = String::new();
-                let mut punctuation = String::new();
-                let mut punctuation_counter = 0;
-
-                // 获取组内的TokenStream并再次遍历
-                let inner_tokens = group.stream();
-                for inner_tt in inner_tokens {
-                    match inner_tt {
-                        // ref 模式 https://rustwiki.org/zh-CN/rust-by-example/scope/borrow/ref.html
-                        proc_macro2::TokenTree::Ident(ref ident) => {
-                            if punctuation.is_empty() {
-                                value = ident.clone().to_string();
-                            } else {
-                                if punctuation_counter >= 1 {
-                                    key = key + &*ident.clone().to_string();
-                                }
+        // 遍历TokenTree::Group下的TokenStream, 忽略非Group类型的TokenTree
+        if let proc_macro2::TokenTree::Group(ref group) = token {
+            let mut key = String::new();
+            let mut value = String::new();
+            let mut punctuation = String::new();
+            let mut punctuation_counter = 0;
+
+            // 获取组内的TokenStream并再次遍历
+            let inner_tokens = group.stream();
+            for inner_tt in inner_tokens {
+                match inner_tt {
+                    // ref 模式 https://rustwiki.org/zh-CN/rust-by-example/scope/borrow/ref.html
+                    proc_macro2::TokenTree::Ident(ref ident) => {
+                        if punctuation.is_empty() {
+                            value = ident.clone().to_string();
+                        } else {
+                            if punctuation_counter >= 1 {
+                                key += &*ident.clone().to_string();
                             }
                         }
-                        proc_macro2::TokenTree::Punct(ref punct) => {
-                            if punct.to_string() == ":" {
-                                punctuation_counter += 1;
-                                punctuation = punct.clone().to_string();
-                                if punctuation_counter > 1 {
-                                    key = key + &*punct.clone().to_string();
-                                }
-                            } else if punct.to_string() == "," {
-                                args_map.insert(key.clone(), value.clone());
-                                key = String::new();
-                                value = String::new();
-                                punctuation = String::new();
-                                punctuation_counter = 0;
-                            } else {
-                                key = key + &*punct.clone().to_string();
+                    }
+                    proc_macro2::TokenTree::Punct(ref punct) => {
+                        if punct.to_string() == ":" {
+                            punctuation_counter += 1;
+                            punctuation = punct.clone().to_string();
+                            if punctuation_counter > 1 {
+                                key += &*punct.clone().to_string();
                             }
+                        } else if punct.to_string() == "," {
+                            args_map.insert(key.clone(), value.clone());
+                            key = String::new();
+                            value = String::new();
+                            punctuation = String::new();
+                            punctuation_counter = 0;
+                        } else {
+                            key += &*punct.clone().to_string();
                         }
-                        // 可以根据需要处理更多类型...
-                        _ => (), // 处理其他类型或忽略
                     }
+                    // 可以根据需要处理更多类型...
+                    _ => (), // 处理其他类型或忽略
                 }
-                if !key.is_empty() && !value.is_empty() {
-                    args_map.insert(key.clone(), value.clone());
-                }
             }
-            // 处理其他类型的TokenTree...
-            _ => (), // 或者忽略非Group类型的TokenTree
+            if !key.is_empty() && !value.is_empty() {
+                args_map.insert(key.clone(), value.clone());
+            }
         }
     }
     args_map
@@ -0,0 +1,285 @@
+use crate::cmd::error::{Error, Result};
+use regex::Regex;
+
+///
+/// DDL 中带注释的列
+///
+pub(crate) struct Column {
+    pub table: String,
+    pub column: String,
+    pub comment: String,
+    /// 注释所在行
+    pub line: usize,
+}
+
+///
+/// 离线解析 DDL 中的列注释, 按出现顺序返回
+///
+/// 支持 `CREATE TABLE` 中的 `COMMENT '...'` (MySQL) 和
+/// `COMMENT ON COLUMN t.c IS '...'` (PostgreSQL)
+///
+pub(crate) fn columns(path: &str, sql: &str) -> Result<Vec<Column>> {
+    let sql = strip_comments(sql);
+    let sql = sql.as_str();
+    let mut columns = vec![];
+    let create = Regex::new(
+        r#"(?i)\bCREATE\s+(?:TEMPORARY\s+)?TABLE\s+(?:IF\s+NOT\s+EXISTS\s+)?([`"\w.]+)\s*\("#,
+    )
+    .unwrap();
+    let comment = Regex::new(r"(?is)\bCOMMENT\s*=?\s*'").unwrap();
+    for c in create.captures_iter(sql) {
+        let table = unquote(&c[1]);
+        let open = c.get(0).map_or(0, |m| m.end());
+        let close = closing(sql, open).ok_or_else(|| {
+            Error::syntax(
+                path,
+                line_of(sql, open),
+                format!("CREATE TABLE {} 缺少右括号", table),
+            )
+        })?;
+        for (start, definition) in split(sql, open, close) {
+            let Some(name) = definition.split_whitespace().next() else {
+                continue;
+            };
+            if is_constraint(name) {
+                continue;
+            }
+            let Some(m) = comment.find(definition) else {
+                continue;
+            };
+            let at = start + m.end();
+            let text = literal(sql, at)
+                .ok_or_else(|| Error::syntax(path, line_of(sql, at), "列注释缺少结束的单引号"))?;
+            columns.push(Column {
+                table: table.clone(),
+                column: unquote(name),
+                comment: text,
+                line: line_of(sql, at),
+            });
+        }
+    }
+
+    let comment_on =
+        Regex::new(r#"(?i)\bCOMMENT\s+ON\s+COLUMN\s+([`"\w.]+)\.([`"\w]+)\s+IS\s+'"#).unwrap();
+    for c in comment_on.captures_iter(sql) {
+        let at = c.get(0).map_or(0, |m| m.end());
+        let text = literal(sql, at)
+            .ok_or_else(|| Error::syntax(path, line_of(sql, at), "列注释缺少结束的单引号"))?;
+        columns.push(Column {
+            table: unquote(&c[1]),
+            column: unquote(&c[2]),
+            comment: text,
+            line: line_of(sql, at),
+        });
+    }
+    columns.sort_by_key(|c| c.line);
+    Ok(columns)
+}
+
+// 将 -- 和 /* */ 注释替换为空格, 保持字节位置和行号不变
+fn strip_comments(sql: &str) -> String {
+    let mut bytes = sql.as_bytes().to_vec();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"' | b'`') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    if bytes[i] == b'\\' && quote == b'\'' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    bytes[i] = b' ';
+                    i += 1;
+                }
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = sql[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| i + end + 4);
+                for b in &mut bytes[i..end] {
+                    if *b != b'\n' {
+                        *b = b' ';
+                    }
+                }
+                i = end;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    // 只把完整的注释替换为 ASCII 空格, 结果仍是合法的 UTF-8
+    String::from_utf8(bytes).unwrap_or_default()
+}
+
+// 去掉引号和 schema 前缀, 如 `shop`.`orders` -> orders
+fn unquote(name: &str) -> String {
+    let name = name.rsplit('.').next().unwrap_or(name);
+    name.trim_matches(|c| c == '`' || c == '"').to_string()
+}
+
+fn is_constraint(word: &str) -> bool {
+    [
+        "PRIMARY",
+        "KEY",
+        "UNIQUE",
+        "INDEX",
+        "CONSTRAINT",
+        "FOREIGN",
+        "CHECK",
+        "FULLTEXT",
+        "SPATIAL",
+    ]
+    .iter()
+    .any(|k| k.eq_ignore_ascii_case(word))
+}
+
+// 从左括号之后查找匹配的右括号, 跳过字符串中的括号
+fn closing(sql: &str, open: usize) -> Option<usize> {
+    let bytes = sql.as_bytes();
+    let mut depth = 1;
+    let mut i = open;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' => i = skip_literal(bytes, i + 1)?,
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+// 按顶层逗号拆分列定义, 返回 (起始位置, 定义)
+fn split(sql: &str, open: usize, close: usize) -> Vec<(usize, &str)> {
+    let bytes = sql.as_bytes();
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut start = open;
+    let mut i = open;
+    while i < close {
+        match bytes[i] {
+            b'\'' => i = skip_literal(bytes, i + 1).unwrap_or(close),
+            b'(' => depth += 1,
+            b')' => depth -= 1,
+            b',' if depth == 0 => {
+                parts.push((start, &sql[start..i]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    parts.push((start, &sql[start..close]));
+    parts
+}
+
+// 返回字符串结束引号的位置, 支持 '' 和 \' 转义
+fn skip_literal(bytes: &[u8], mut i: usize) -> Option<usize> {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'\'' if bytes.get(i + 1) == Some(&b'\'') => i += 1,
+            b'\'' => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+// 读取从 at 开始, 到结束引号为止的字符串内容
+fn literal(sql: &str, at: usize) -> Option<String> {
+    let end = skip_literal(sql.as_bytes(), at)?;
+    Some(
+        sql[at..end]
+            .replace("''", "'")
+            .replace("\\'", "'")
+            .replace("\\\\", "\\"),
+    )
+}
+
+fn line_of(sql: &str, at: usize) -> usize {
+    sql[..at].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(sql: &str) -> Vec<(String, String, String, usize)> {
+        columns("schema.sql", sql)
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.table, c.column, c.comment, c.line))
+            .collect()
+    }
+
+    #[test]
+    fn mysql_column_comments() {
+        let sql = "CREATE TABLE IF NOT EXISTS `shop`.`orders` (\n  `id` bigint COMMENT '主键',\n  `status` tinyint DEFAULT 1 COMMENT '状态:1-待支付,2-已支付',\n  `price` decimal(10,2) COMMENT 'it''s (price)',\n  PRIMARY KEY (`id`)\n) COMMENT='订单';\n";
+        assert_eq!(
+            summary(sql),
+            [
+                ("orders".into(), "id".into(), "主键".into(), 2),
+                (
+                    "orders".into(),
+                    "status".into(),
+                    "状态:1-待支付,2-已支付".into(),
+                    3
+                ),
+                ("orders".into(), "price".into(), "it's (price)".into(), 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn postgres_comment_on_column() {
+        let sql = "CREATE TABLE users (id bigint, type smallint);\nCOMMENT ON COLUMN public.users.type IS '类型:1-普通';\n";
+        assert_eq!(
+            summary(sql),
+            [("users".into(), "type".into(), "类型:1-普通".into(), 2)]
+        );
+    }
+
+    #[test]
+    fn sql_comments_are_skipped() {
+        let sql = "CREATE TABLE orders (\n  -- pay type, see doc (v2)\n  pay_type tinyint COMMENT '支付:1-微信',\n  -- user's status\n  /* status, (old) 'x */ status tinyint COMMENT '状态:1-待支付'\n);\n-- COMMENT ON COLUMN orders.x IS 'y';\n";
+        assert_eq!(
+            summary(sql),
+            [
+                ("orders".into(), "pay_type".into(), "支付:1-微信".into(), 3),
+                ("orders".into(), "status".into(), "状态:1-待支付".into(), 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn comment_markers_inside_literals() {
+        let sql = "CREATE TABLE t (a int DEFAULT '--' COMMENT 'a:1-x /* y */');";
+        assert_eq!(
+            summary(sql),
+            [("t".into(), "a".into(), "a:1-x /* y */".into(), 1)]
+        );
+    }
+
+    #[test]
+    fn missing_closing_paren() {
+        assert!(matches!(
+            columns("schema.sql", "CREATE TABLE t (a int COMMENT 'x'"),
+            Err(Error::Syntax { line: 1, .. })
+        ));
+    }
+}
@@ -1,12 +1,20 @@
-use crate::cmd::file;
+use crate::cmd::error::{Error, Result};
+use crate::cmd::plan::{self, MARKER, OnExists, Plan};
+use crate::cmd::report::Report;
+use crate::cmd::source::Format;
+use crate::cmd::{ddl, file, ident, stub};
 use clap::{Arg, ArgAction, ArgMatches, Command};
-use minijinja::Environment;
 use regex::Regex;
 use serde::Serialize;
-use std::{fs, path::Path};
+use serde_yml::Value;
+use std::str::FromStr;
 
 // cargo run gen enum --out="./enums" --name="-e=order_flow -f=发起订单:selling_assistant-1-销售内勤,sale-2-销售"
 // cargo run gen enum --out="./enums" --file="./src/cmd/enum.md"
+// cargo run gen enum --out="./enums" --file="./enums.yaml"
+// cargo run gen enum --out="./enums" --file="./enums.yaml" --style=native --serde=name
+// cargo run gen enum --out="./enums" --file="./enums.yaml" --style=native --db=sqlx
+// cargo run gen enum --out="./enums" --from-sql="./schema.sql"
 #[allow(dead_code)]
 pub(crate) fn command() -> Command {
     Command::new("enum")
@@ -23,148 +31,1027 @@ pub(crate) fn command() -> Command {
         )
         .arg(
             Arg::new("name")
-                .conflicts_with("file")
+                .conflicts_with_all(["file", "from-sql"])
                 .short('n')
                 .long("name")
                 .help("name"),
         )
         .arg(
             Arg::new("file")
-                .conflicts_with("name")
+                .conflicts_with_all(["name", "from-sql"])
                 .short('f')
                 .long("file")
                 .help("file"),
         )
+        .arg(
+            Arg::new("from-sql")
+                .long("from-sql")
+                .help("SQL schema whose column comments define enums, e.g. 状态:1-待支付,2-已支付"),
+        )
+        .arg(
+            Arg::new("style")
+                .long("style")
+                .value_parser(["const", "native"])
+                .help("Output style: Enum constants or a native Rust enum"),
+        )
+        .arg(
+            Arg::new("serde")
+                .long("serde")
+                .value_parser(["code", "name"])
+                .help("Serialize native enums as their code or name"),
+        )
+        .arg(
+            Arg::new("db")
+                .long("db")
+                .value_parser(["sqlx", "diesel"])
+                .help("Map native enums to integer columns for sqlx or diesel"),
+        )
+        .arg(
+            Arg::new("templates")
+                .long("templates")
+                .help("Templates Directory"),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .action(ArgAction::SetTrue)
+                .help("Print a diff instead of writing files"),
+        )
+        .arg(
+            Arg::new("check")
+                .long("check")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("dry-run")
+                .help("Fail with a diff if the generated files are out of date"),
+        )
+        .arg(
+            Arg::new("on-exists")
+                .long("on-exists")
+                .value_parser(["skip", "overwrite", "error", "merge"])
+                .help("Policy for existing output files"),
+        )
 }
 
 #[derive(Serialize)]
 struct EnumStub {
     pub doc: String,
+    /// `--style=native` 时的枚举类型名
+    pub name: String,
     pub rows: Vec<Enum>,
+    /// 枚举上的 Rust 属性, 如 `derive(Default)`
+    pub attrs: Vec<String>,
+    /// `--serde` 的取值, 未设置时为空
+    pub serde: String,
+    /// `--db` 的取值, 未设置时为空
+    pub db: String,
 }
 
 #[derive(Serialize)]
 struct Enum {
     pub name: String,
+    /// `--style=native` 时的变体名
+    pub variant: String,
     pub code: i32,
     pub message: String,
+    /// 变体或常量上的 Rust 属性
+    pub attrs: Vec<String>,
 }
 #[allow(dead_code)]
-pub(crate) fn execute(arg_matches: &ArgMatches) {
-    let out_file = crate::cmd::r#gen::out_file(arg_matches);
-    let import_mod_file = out_file.clone() + "/mod.rs";
-
-    let file = crate::cmd::r#gen::file(arg_matches);
-    if !file.is_empty() {
-        let content = fs::read_to_string(file.clone().as_str())
-            .expect(format!("读取{}文件失败", import_mod_file.clone()).as_str());
-        for line in content.lines() {
+pub(crate) fn execute(arg_matches: &ArgMatches) -> Result<()> {
+    EnumGen::new()
+        .out(crate::cmd::r#gen::out_file(arg_matches))
+        .name(crate::cmd::r#gen::name(arg_matches))
+        .file(crate::cmd::r#gen::file(arg_matches))
+        .from_sql(
+            arg_matches
+                .get_one::<String>("from-sql")
+                .cloned()
+                .unwrap_or_default(),
+        )
+        .templates(crate::cmd::r#gen::templates(arg_matches))
+        .style(
+            arg_matches
+                .get_one::<String>("style")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or_default(),
+        )
+        .serde_opt(
+            arg_matches
+                .get_one::<String>("serde")
+                .and_then(|s| s.parse().ok()),
+        )
+        .db_opt(
+            arg_matches
+                .get_one::<String>("db")
+                .and_then(|s| s.parse().ok()),
+        )
+        .dry_run(arg_matches.get_flag("dry-run"))
+        .check(arg_matches.get_flag("check"))
+        .on_exists_opt(crate::cmd::r#gen::on_exists(arg_matches))
+        .run()?;
+    Ok(())
+}
+
+///
+/// 枚举输出风格
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EnumStyle {
+    /// `hirust_gen::Enum` 常量
+    #[default]
+    Const,
+    /// `#[repr(i32)]` 枚举, 实现 `TryFrom<i32>`、`FromStr` 和 `Display`
+    Native,
+}
+
+impl FromStr for EnumStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "const" => Ok(EnumStyle::Const),
+            "native" => Ok(EnumStyle::Native),
+            _ => Err(format!("unknown enum style: {}", s)),
+        }
+    }
+}
+
+///
+/// `--style=native` 时 serde 的序列化方式
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnumSerde {
+    /// 序列化为 code, 如 `1`
+    Code,
+    /// 序列化为名称, 如 `"selling_assistant"`
+    Name,
+}
+
+impl FromStr for EnumSerde {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "code" => Ok(EnumSerde::Code),
+            "name" => Ok(EnumSerde::Name),
+            _ => Err(format!("unknown enum serde: {}", s)),
+        }
+    }
+}
+
+///
+/// `--style=native` 时按 code 映射整数列的数据库库
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnumDb {
+    /// 实现 sqlx 的 `Type`、`Encode` 和 `Decode`
+    Sqlx,
+    /// 实现 diesel 的 `ToSql` 和 `FromSql`, 对应 `Integer` 列
+    Diesel,
+}
+
+impl FromStr for EnumDb {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "sqlx" => Ok(EnumDb::Sqlx),
+            "diesel" => Ok(EnumDb::Diesel),
+            _ => Err(format!("unknown enum db: {}", s)),
+        }
+    }
+}
+
+///
+/// 枚举生成器, `name`、`file` 与 `from_sql` 三选一, 优先级为 `from_sql` > `file` > `name`
+///
+/// Examples
+///```no_run
+/// let report = hirust_gen::EnumGen::new()
+///     .out("./enums")
+///     .name("-e=yes_or_no -f=是否:no-1-否,yes-2-是")
+///     .run()?;
+/// # Ok::<(), hirust_gen::Error>(())
+///```
+///
+#[derive(Debug, Clone, Default)]
+pub struct EnumGen {
+    out: String,
+    name: String,
+    file: String,
+    from_sql: String,
+    templates: String,
+    style: EnumStyle,
+    serde: Option<EnumSerde>,
+    db: Option<EnumDb>,
+    dry_run: bool,
+    check: bool,
+    on_exists: Option<OnExists>,
+}
+
+impl EnumGen {
+    pub fn new() -> Self {
+        EnumGen::default()
+    }
+
+    /// 输出目录
+    pub fn out(mut self, out: impl Into<String>) -> Self {
+        self.out = out.into();
+        self
+    }
+
+    /// 单个枚举定义, 如 `-e=order_flow -f=发起订单:selling_assistant-1-销售内勤,sale-2-销售`
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// 枚举定义文件, .yaml/.yml/.toml/.json 为结构化定义, 其他为每行一个 `--name="..."` 的旧版格式
+    ///
+    /// ```yaml
+    /// enums:
+    ///   - name: order_flow
+    ///     doc: 发起订单
+    ///     variants:
+    ///       - { name: selling_assistant, code: 1, message: 销售内勤 }
+    ///       - { name: sale, code: 2, message: 销售 }
+    /// ```
+    ///
+    /// `attrs` 为附加的 Rust 属性列表, 变体上的属性在 `EnumStyle::Const` 时加在常量上,
+    /// 枚举上的属性只能用于 `EnumStyle::Native`
+    pub fn file(mut self, file: impl Into<String>) -> Self {
+        self.file = file.into();
+        self
+    }
+
+    /// 建表 SQL, 注释形如 `状态:1-待支付,2-已支付` 的列各生成一个 `<table>_<column>` 枚举
+    ///
+    /// ```sql
+    /// CREATE TABLE orders (
+    ///   status tinyint NOT NULL COMMENT '状态:1-待支付,2-已支付'
+    /// );
+    /// ```
+    pub fn from_sql(mut self, from_sql: impl Into<String>) -> Self {
+        self.from_sql = from_sql.into();
+        self
+    }
+
+    /// 自定义模板目录
+    pub fn templates(mut self, templates: impl Into<String>) -> Self {
+        self.templates = templates.into();
+        self
+    }
+
+    /// 输出风格, 默认 `EnumStyle::Const`
+    pub fn style(mut self, style: EnumStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// 为 `EnumStyle::Native` 生成 serde 的 `Serialize`/`Deserialize` 实现
+    pub fn serde(mut self, serde: EnumSerde) -> Self {
+        self.serde = Some(serde);
+        self
+    }
+
+    fn serde_opt(mut self, serde: Option<EnumSerde>) -> Self {
+        self.serde = serde;
+        self
+    }
+
+    /// 为 `EnumStyle::Native` 生成数据库整数列的映射
+    pub fn db(mut self, db: EnumDb) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    fn db_opt(mut self, db: Option<EnumDb>) -> Self {
+        self.db = db;
+        self
+    }
+
+    /// 只输出 diff, 不写文件
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// 检查已有文件是否与生成结果一致, 不一致时返回 [`Error::Stale`](crate::Error::Stale), 不写文件
+    pub fn check(mut self, check: bool) -> Self {
+        self.check = check;
+        self
+    }
+
+    /// 输出文件已存在时的处理策略, 默认 `OnExists::Skip`
+    pub fn on_exists(mut self, on_exists: OnExists) -> Self {
+        self.on_exists = Some(on_exists);
+        self
+    }
+
+    fn on_exists_opt(mut self, on_exists: Option<OnExists>) -> Self {
+        self.on_exists = on_exists;
+        self
+    }
+
+    // --check 时按已有文件是否带标记比较, 见 plan::check_policy
+    fn policy(&self, existing: Option<&str>) -> OnExists {
+        let on_exists = self.on_exists.unwrap_or(OnExists::Skip);
+        if self.check {
+            plan::check_policy(on_exists, existing)
+        } else {
+            on_exists
+        }
+    }
+
+    pub fn run(&self) -> Result<Report> {
+        if self.serde.is_some() && self.style != EnumStyle::Native {
+            return Err(Error::syntax(
+                "--serde",
+                0,
+                "只能与 --style=native 一起使用",
+            ));
+        }
+        if self.db.is_some() && self.style != EnumStyle::Native {
+            return Err(Error::syntax("--db", 0, "只能与 --style=native 一起使用"));
+        }
+        let out_file = self.out.clone();
+        let registry = stub::Registry::load(self.templates.as_str())?;
+        let import_mod_file = out_file.clone() + "/mod.rs";
+
+        let mut plan = Plan::default();
+        let file = self.file.clone();
+        let mut definitions = vec![];
+        if !self.from_sql.is_empty() {
+            definitions = parse_sql(self.from_sql.as_str(), self.style)?;
+        } else if let Some(format) = Format::from_path(file.as_str()) {
+            definitions = parse_file(format, file.as_str(), self.style)?;
+        } else if !file.is_empty() {
+            let content = file::read_file(file.as_str())?;
             let pattern = format!("--name={}-e=(.*)+-f=(.*){}", '"', '"');
             let re = Regex::new(pattern.as_str()).unwrap();
-            match re.captures(line) {
-                Some(c) => {
-                    let mut name: String = c.get(0).map_or("", |m| m.as_str()).parse().unwrap();
+            for (i, line) in content.lines().enumerate() {
+                if let Some(c) = re.captures(line) {
+                    let mut name: String = c.get(0).map_or("", |m| m.as_str()).to_string();
                     name = name.replace("--name=\"", "");
                     name = name.replace("\"", "");
-                    single(out_file.clone(), import_mod_file.clone(), name.clone())
+                    definitions.push(parse_dsl(name, (file.as_str(), i + 1), self.style)?);
                 }
-                None => {}
             }
+        } else {
+            definitions.push(parse_dsl(self.name.clone(), ("--name", 0), self.style)?);
         }
-    } else {
-        let name: String = crate::cmd::r#gen::name(arg_matches);
-
-        single(out_file.clone(), import_mod_file.clone(), name)
+        for definition in definitions {
+            emit(
+                &registry,
+                &mut plan,
+                self,
+                out_file.as_str(),
+                import_mod_file.as_str(),
+                definition,
+            )?;
+        }
+        plan.commit(self.dry_run, self.check)
     }
 }
 
-fn single(mut out_file: String, import_mod_file: String, mut name: String) {
+// 一个枚举定义
+struct Definition {
+    // 模块名
+    name: String,
+    // --style=native 时的枚举类型名
+    type_name: String,
+    doc: String,
+    rows: Vec<Enum>,
+    attrs: Vec<String>,
+}
+
+// 解析旧版 DSL: -e=<name> -f=<doc>:<name>-<code>-<message>,...
+// source: 定义来源(文件, 行号), 用于错误提示
+fn parse_dsl(mut name: String, source: (&str, usize), style: EnumStyle) -> Result<Definition> {
+    let (path, line) = source;
     name = name.replace("\\", "");
     name = name.replace("\n", "");
     name = name.replace("\r", "");
-    name = name.trim().parse().unwrap();
+    name = name.trim().to_string();
 
-    let re = Regex::new(r"(-e=[a-zA-Z_]+\s*-f=).*").unwrap();
-    if !re.is_match(&*name) {
-        panic!("regexp err")
+    let re = Regex::new(r"(-e=\S+\s*-f=).*").unwrap();
+    if !re.is_match(&name) {
+        return Err(Error::syntax(
+            path,
+            line,
+            format!(
+                "格式应为 -e=<name> -f=<doc>:<name>-<code>-<message>,...: {}",
+                name
+            ),
+        ));
     }
     name = name.replace("-e=", "");
     let names: Vec<String> = name.split("-f=").map(|x| x.to_string()).collect();
     if names.len() != 2 {
-        panic!("name err")
+        return Err(Error::syntax(path, line, "-f= 只能出现一次"));
     }
-    let crate_dir: String = names[0].trim().parse().unwrap();
+    let syntax = |message: String| Error::syntax(path, line, message);
+    let crate_dir = ident::snake(names[0].trim()).map_err(syntax)?;
+    let type_name = native(style, names[0].trim()).map_err(syntax)?;
 
     let docs: Vec<String> = names[1].split(":").map(|x| x.to_string()).collect();
-    let doc: String = docs[0].trim().parse().unwrap();
+    if docs.len() != 2 {
+        return Err(Error::syntax(
+            path,
+            line,
+            format!("{} 缺少 <doc>: 前缀", names[1].trim()),
+        ));
+    }
+    let doc: String = docs[0].trim().to_string();
 
     let mut rows: Vec<Enum> = vec![];
     let es: Vec<String> = docs[1].split(",").map(|x| x.to_string()).collect();
+    // name 可以是 kebab-case, 以第一个 -<整数>- 分隔
+    let re = Regex::new(r"^(.+?)-\s*(-?\d+)\s*-(.*)$").unwrap();
     for e in es {
-        let em: Vec<String> = e.split("-").map(|x| x.to_string()).collect();
-        let name: String = em[0].trim().to_string();
-        let code: i32 = em[1].trim().parse().unwrap();
-        let message: String = em[2].replace("\n", "").replace("\r", "").trim().to_string();
+        let Some(em) = re.captures(e.trim()) else {
+            return Err(Error::syntax(
+                path,
+                line,
+                format!("{} 应为 <name>-<code>-<message>", e.trim()),
+            ));
+        };
+        let name: String = em[1].trim().to_string();
+        let code: i32 = em[2].parse().map_err(|_| {
+            Error::syntax(path, line, format!("{} 的 code 不是整数: {}", name, &em[2]))
+        })?;
+        let message: String = em[3].replace("\n", "").replace("\r", "").trim().to_string();
         rows.push(Enum {
-            name: name.to_uppercase(),
+            name: ident::constant(name.as_str()).map_err(syntax)?,
+            variant: native(style, name.as_str()).map_err(syntax)?,
             code,
             message,
+            attrs: vec![],
         });
     }
+    Ok(Definition {
+        name: crate_dir,
+        type_name,
+        doc,
+        rows,
+        attrs: vec![],
+    })
+}
 
-    let stub = EnumStub { doc, rows };
+// 解析结构化定义文件 (.yaml/.yml/.toml/.json)
+fn parse_file(format: Format, path: &str, style: EnumStyle) -> Result<Vec<Definition>> {
+    let content = file::read_file(path)?;
+    let value = format.parse(path, content.as_str())?;
+    let syntax = |at: &str, message: &str| Error::syntax(path, 0, format!("{}: {}", at, message));
+    let enums = value
+        .get("enums")
+        .and_then(|v| v.as_sequence())
+        .ok_or_else(|| syntax("enums", "缺少枚举列表"))?;
+    let mut definitions = vec![];
+    for (i, def) in enums.iter().enumerate() {
+        let at = format!("enums[{}]", i);
+        let name = def
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| syntax(at.as_str(), "name 必须是字符串"))?
+            .to_string();
+        let at = format!("enums[{}]({})", i, name);
+        let type_name =
+            native(style, name.as_str()).map_err(|m| syntax(at.as_str(), m.as_str()))?;
+        let name = ident::snake(name.as_str()).map_err(|m| syntax(at.as_str(), m.as_str()))?;
+        let doc = match def.get("doc") {
+            None => String::new(),
+            Some(v) => v
+                .as_str()
+                .ok_or_else(|| syntax(at.as_str(), "doc 必须是字符串"))?
+                .to_string(),
+        };
+        let variants = def
+            .get("variants")
+            .and_then(|v| v.as_sequence())
+            .ok_or_else(|| syntax(at.as_str(), "缺少 variants 列表"))?;
+        let mut rows = vec![];
+        for (j, variant) in variants.iter().enumerate() {
+            let at = format!("{}.variants[{}]", at, j);
+            let name = variant
+                .get("name")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| syntax(at.as_str(), "name 必须是字符串"))?;
+            let code = variant
+                .get("code")
+                .and_then(|v| v.as_i64())
+                .and_then(|v| i32::try_from(v).ok())
+                .ok_or_else(|| syntax(at.as_str(), "code 必须是 i32 整数"))?;
+            let message = variant
+                .get("message")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| syntax(at.as_str(), "message 必须是字符串"))?;
+            rows.push(Enum {
+                name: ident::constant(name).map_err(|m| syntax(at.as_str(), m.as_str()))?,
+                variant: native(style, name).map_err(|m| syntax(at.as_str(), m.as_str()))?,
+                code,
+                message: message.to_string(),
+                attrs: attrs(variant, at.as_str(), path)?,
+            });
+        }
+        let attrs = attrs(def, at.as_str(), path)?;
+        if !attrs.is_empty() && style == EnumStyle::Const {
+            return Err(syntax(
+                at.as_str(),
+                "attrs 需要 --style=native, 常量风格没有可附加属性的枚举类型",
+            ));
+        }
+        definitions.push(Definition {
+            attrs,
+            name,
+            type_name,
+            doc,
+            rows,
+        });
+    }
+    Ok(definitions)
+}
 
-    let binding = file!();
-    let path = Path::new(&binding);
-    let stub_path = format!("{}", path.parent().unwrap().display());
+// 解析建表 SQL, 每个注释符合枚举格式的列生成一个枚举, 其他列忽略
+fn parse_sql(path: &str, style: EnumStyle) -> Result<Vec<Definition>> {
+    let content = file::read_file(path)?;
+    let mut definitions = vec![];
+    for column in ddl::columns(path, content.as_str())? {
+        let Some((doc, items)) = parse_comment(column.comment.as_str()) else {
+            continue;
+        };
+        let syntax = |message: String| Error::syntax(path, column.line, message);
+        let name = format!("{}_{}", column.table, column.column);
+        let mut rows = vec![];
+        for (name, code, message) in items {
+            // 只有 code-message 时以 code 命名, 如 CODE_1
+            let name = name.unwrap_or_else(|| match code {
+                code if code < 0 => format!("code_n{}", code.unsigned_abs()),
+                code => format!("code_{}", code),
+            });
+            rows.push(Enum {
+                name: ident::constant(name.as_str()).map_err(syntax)?,
+                variant: native(style, name.as_str()).map_err(syntax)?,
+                code,
+                message,
+                attrs: vec![],
+            });
+        }
+        definitions.push(Definition {
+            type_name: native(style, name.as_str()).map_err(syntax)?,
+            name: ident::snake(name.as_str()).map_err(syntax)?,
+            doc,
+            rows,
+            attrs: vec![],
+        });
+    }
+    if definitions.is_empty() {
+        return Err(Error::syntax(path, 0, "没有找到注释为枚举格式的列"));
+    }
+    Ok(definitions)
+}
 
-    let stub_template = fs::read_to_string(stub_path + "/../stubs/enum.stub").unwrap();
+// 列注释中的枚举值 (name, code, message), name 可省略
+type CommentItem = (Option<String>, i32, String);
 
-    let mut env = Environment::new();
-    env.add_template("stub", stub_template.as_str()).unwrap();
-    let template = env.get_template("stub").unwrap();
+// 列注释: <doc>:<code>-<message>,... 或 <doc>:<name>-<code>-<message>,...
+// 兼容全角冒号和逗号, 不符合格式时返回 None
+fn parse_comment(comment: &str) -> Option<(String, Vec<CommentItem>)> {
+    let (doc, body) = comment.split_once([':', '：'])?;
+    let re = Regex::new(r"^(?:([A-Za-z_][\w-]*?)\s*-\s*)?(-?\d+)\s*-(.+)$").unwrap();
+    let mut items = vec![];
+    for item in body.split([',', '，', ';', '；']) {
+        let item = item.trim();
+        if item.is_empty() {
+            continue;
+        }
+        let c = re.captures(item)?;
+        items.push((
+            c.get(1).map(|m| m.as_str().to_string()),
+            c[2].parse().ok()?,
+            c[3].trim().to_string(),
+        ));
+    }
+    if items.is_empty() {
+        return None;
+    }
+    Some((doc.trim().to_string(), items))
+}
 
-    let mod_content = template.render(stub).unwrap();
+// --style=native 时的类型名或变体名, 常量风格不使用
+fn native(style: EnumStyle, name: &str) -> std::result::Result<String, String> {
+    match style {
+        EnumStyle::Const => Ok(String::new()),
+        EnumStyle::Native => ident::variant(name),
+    }
+}
 
-    out_file = out_file + "/" + crate_dir.clone().as_str();
+// 额外的 Rust 属性, 不含 #[], 如 attrs: ["derive(Default)"]
+fn attrs(value: &Value, at: &str, path: &str) -> Result<Vec<String>> {
+    let syntax = |message: String| Error::syntax(path, 0, format!("{}: {}", at, message));
+    let Some(value) = value.get("attrs") else {
+        return Ok(vec![]);
+    };
+    let attrs: Vec<String> = value
+        .as_sequence()
+        .and_then(|attrs| {
+            attrs
+                .iter()
+                .map(|a| a.as_str().map(|a| a.trim().to_string()))
+                .collect()
+        })
+        .ok_or_else(|| syntax(String::from("attrs 必须是字符串列表")))?;
+    for attr in &attrs {
+        syn::parse_str::<syn::Meta>(attr)
+            .map_err(|e| syntax(format!("attrs 中的 {} 不是合法的 Rust 属性: {}", attr, e)))?;
+    }
+    Ok(attrs)
+}
 
-    let out_file_dir_list: Vec<String> =
-        out_file.clone().split("/").map(|x| x.to_string()).collect();
-    let mut out_file_dir_temp = String::new();
-    for out_file_dir in out_file_dir_list {
-        out_file_dir_temp =
-            String::from(out_file_dir_temp.to_owned() + out_file_dir.as_str() + "/");
-        if !std::path::Path::new(out_file_dir_temp.clone().as_str()).exists() {
-            fs::create_dir(out_file.as_str())
-                .expect(format!("创建{}目录失败", out_file_dir_temp.clone()).as_str());
-        }
+fn emit(
+    registry: &stub::Registry,
+    plan: &mut Plan,
+    enum_gen: &EnumGen,
+    out_file: &str,
+    import_mod_file: &str,
+    definition: Definition,
+) -> Result<()> {
+    let Definition {
+        name: crate_dir,
+        type_name: name,
+        doc,
+        mut rows,
+        attrs,
+    } = definition;
+    let out_file = format!("{}/{}/mod.rs", out_file, ident::unraw(crate_dir.as_str()));
+    let existing = plan.read(out_file.as_str())?;
+    let on_exists = enum_gen.policy(existing.as_deref());
+    // 只有显式 merge 时合并枚举值, --check 的 merge 比较只保留手写代码
+    if enum_gen.on_exists == Some(OnExists::Merge)
+        && let Some(existing) = existing
+    {
+        rows = merge_rows(existing.as_str(), rows, enum_gen.style);
     }
 
-    out_file = out_file + "/mod.rs";
-    if !std::path::Path::new(out_file.clone().as_str()).exists() {
-        file::create_file(out_file.as_str());
+    let syntax = |message: String| Error::syntax(out_file.as_str(), 0, message);
+    check_rows(enum_gen.style, crate_dir.as_str(), name.as_str(), &rows).map_err(syntax)?;
+    let template = match enum_gen.style {
+        EnumStyle::Const => stub::ENUM,
+        EnumStyle::Native => stub::ENUM_NATIVE,
+    };
+
+    let stub = EnumStub {
+        doc,
+        name,
+        rows,
+        attrs,
+        serde: match enum_gen.serde {
+            Some(EnumSerde::Code) => String::from("code"),
+            Some(EnumSerde::Name) => String::from("name"),
+            None => String::new(),
+        },
+        db: match enum_gen.db {
+            Some(EnumDb::Sqlx) => String::from("sqlx"),
+            Some(EnumDb::Diesel) => String::from("diesel"),
+            None => String::new(),
+        },
+    };
+
+    let mod_content = registry.render(template, stub)?;
+
+    // 写文件
+    plan.write_with(out_file.as_str(), mod_content.as_str(), on_exists)?;
+
+    // mod导入
+    let mut content = plan.read(import_mod_file)?.unwrap_or_default();
+    if !content.contains(format!("pub mod {};", crate_dir).as_str()) {
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
         // 写文件
-        file::write_file(out_file.as_str(), mod_content.as_str());
-    } else {
-        println!("{}已经存在", out_file.clone());
+        plan.write(
+            import_mod_file,
+            format!("{}pub mod {};", content, crate_dir).as_str(),
+        )?;
     }
+    Ok(())
+}
 
-    // mod导入
-    if !std::path::Path::new(import_mod_file.clone().as_str()).exists() {
-        file::create_file(import_mod_file.clone().as_str());
+// 大小写转换和生成 CODE_<n> 名称之后, 常量名 (native 为变体名) 和 code 不能重复
+// 原生枚举至少需要一个变体
+fn check_rows(
+    style: EnumStyle,
+    module: &str,
+    type_name: &str,
+    rows: &[Enum],
+) -> std::result::Result<(), String> {
+    let (prefix, ident): (&str, fn(&Enum) -> &str) = match style {
+        EnumStyle::Const => (module, |row| row.name.as_str()),
+        EnumStyle::Native => (type_name, |row| row.variant.as_str()),
+    };
+    if style == EnumStyle::Native && rows.is_empty() {
+        return Err(format!("{} 至少需要一个枚举值", type_name));
     }
-    let content = fs::read_to_string(import_mod_file.clone().as_str())
-        .expect(format!("读取{}文件失败", import_mod_file.clone()).as_str());
-    if !content.contains(format!("pub mod {};", crate_dir.clone()).as_str()) {
-        // 写文件
-        file::write_file(
-            import_mod_file.as_str(),
-            format!(
-                "{}\n{}",
-                content,
-                format!("pub mod {};", crate_dir.clone()).as_str()
+    for (i, row) in rows.iter().enumerate() {
+        if rows[..i].iter().any(|r| ident(r) == ident(row)) {
+            return Err(format!("{}::{} 重复定义", prefix, ident(row)));
+        }
+        if let Some(other) = rows[..i].iter().find(|r| r.code == row.code) {
+            return Err(format!(
+                "{}::{} 与 {}::{} 的 code 重复: {}",
+                prefix,
+                ident(row),
+                prefix,
+                ident(other),
+                row.code
+            ));
+        }
+    }
+    Ok(())
+}
+
+// 以已有枚举值为基础, 更新code和message, 追加新增的枚举值
+// 已有的值按常量名 (native 为变体名) 或 code 匹配, 未匹配的保留原有属性
+fn merge_rows(existing: &str, rows: Vec<Enum>, style: EnumStyle) -> Vec<Enum> {
+    let generated = existing.split(MARKER).next().unwrap_or_default();
+    let mut merged: Vec<Enum> = match style {
+        EnumStyle::Const => Regex::new(
+            r#"(?m)((?:^[ \t]*#\[.*\]\n)*)^[ \t]*pub const ([A-Za-z0-9_]+): Enum = Enum::Body \{ code: (-?\d+), message: ("(?:[^"\\]|\\.)*") \};"#,
+        )
+        .unwrap()
+        .captures_iter(generated)
+        .map(|c| Enum {
+            name: c[2].to_string(),
+            variant: String::new(),
+            code: c[3].parse().unwrap_or_default(),
+            message: unquote(&c[4]),
+            // 模板在每个常量上生成的 allow(dead_code) 不是附加属性
+            attrs: existing_attrs(&c[1])
+                .into_iter()
+                .filter(|attr| attr != "allow(dead_code)")
+                .collect(),
+        })
+        .collect(),
+        // 变体上方的文档注释即 message, 中间可以有属性, 名称取自 FromStr 的匹配分支
+        EnumStyle::Native => {
+            let names: Vec<(String, String)> =
+                Regex::new(r#"(?m)^[ \t]*("(?:[^"\\]|\\.)*") => Ok\(\w+::((?:r#)?\w+)\),"#)
+                    .unwrap()
+                    .captures_iter(generated)
+                    .map(|c| (c[2].to_string(), unquote(&c[1])))
+                    .collect();
+            Regex::new(
+                r"(?m)((?:^[ \t]*///.*\n)*)((?:^[ \t]*#\[.*\]\n)*)^[ \t]*((?:r#)?[A-Za-z0-9_]+) = (-?\d+),",
             )
-            .as_str(),
+            .unwrap()
+            .captures_iter(generated)
+            .map(|c| {
+                let variant = c[3].to_string();
+                let name = names
+                    .iter()
+                    .find(|(v, _)| *v == variant)
+                    .map(|(_, name)| name.to_uppercase())
+                    .unwrap_or_else(|| ident::constant(ident::unraw(&variant)).unwrap_or_default());
+                let message = c[1]
+                    .lines()
+                    .map(|line| {
+                        let line = line.trim_start().trim_start_matches("///");
+                        line.strip_prefix(' ').unwrap_or(line)
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Enum {
+                    name,
+                    variant,
+                    code: c[4].parse().unwrap_or_default(),
+                    message,
+                    attrs: existing_attrs(&c[2]),
+                }
+            })
+            .collect()
+        }
+    };
+    let key = |e: &Enum| match style {
+        EnumStyle::Const => e.name.clone(),
+        EnumStyle::Native => e.variant.clone(),
+    };
+    for row in rows {
+        let at = merged
+            .iter()
+            .position(|e| key(e) == key(&row))
+            .or_else(|| merged.iter().position(|e| e.code == row.code));
+        match at {
+            Some(at) => merged[at] = row,
+            None => merged.push(row),
+        }
+    }
+    merged
+}
+
+// 已有文件中的属性行, 去掉 #[ 和 ]
+fn existing_attrs(lines: &str) -> Vec<String> {
+    lines
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("#[")?.strip_suffix(']'))
+        .map(|attr| attr.to_string())
+        .collect()
+}
+
+// 已有文件中的字符串字面量
+fn unquote(literal: &str) -> String {
+    syn::parse_str::<syn::LitStr>(literal)
+        .map(|s| s.value())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::file::scratch;
+
+    #[test]
+    fn comment_with_codes_only() {
+        let (doc, items) = parse_comment("状态:1-待支付, 2-已支付,-1-已删除").unwrap();
+        assert_eq!(doc, "状态");
+        assert_eq!(
+            items,
+            [
+                (None, 1, String::from("待支付")),
+                (None, 2, String::from("已支付")),
+                (None, -1, String::from("已删除")),
+            ]
         );
     }
+
+    #[test]
+    fn comment_with_names() {
+        let (doc, items) = parse_comment("支付方式：wechat-1-微信，ali-pay-2-支付-宝").unwrap();
+        assert_eq!(doc, "支付方式");
+        assert_eq!(
+            items,
+            [
+                (Some(String::from("wechat")), 1, String::from("微信")),
+                (Some(String::from("ali-pay")), 2, String::from("支付-宝")),
+            ]
+        );
+    }
+
+    #[test]
+    fn comment_without_enum() {
+        assert!(parse_comment("主键").is_none());
+        assert!(parse_comment("price, in (cents): x").is_none());
+        assert!(parse_comment("状态:").is_none());
+        assert!(parse_comment("状态:1-待支付,其他").is_none());
+    }
+
+    #[test]
+    fn merge_native_from_sql_is_stable() {
+        let dir = scratch("merge-native");
+        let sql = format!("{}/schema.sql", dir);
+        std::fs::write(
+            &sql,
+            "CREATE TABLE orders (\n  status tinyint COMMENT '状态:1-待支付,2-已支付,-1-已删除'\n);\n",
+        )
+        .unwrap();
+        let out = format!("{}/enums", dir);
+        let generate = |on_exists| {
+            EnumGen::new()
+                .out(out.as_str())
+                .from_sql(sql.as_str())
+                .style(EnumStyle::Native)
+                .on_exists(on_exists)
+                .run()
+                .unwrap();
+            std::fs::read_to_string(format!("{}/orders_status/mod.rs", out)).unwrap()
+        };
+        let first = generate(OnExists::Overwrite);
+        assert!(first.contains("Code1 = 1,"));
+        assert_eq!(generate(OnExists::Merge), first);
+        assert_eq!(generate(OnExists::Merge), first);
+    }
+
+    #[test]
+    fn merge_keeps_attrs_of_existing_rows() {
+        let existing = "pub enum St {\n    /// 甲\n    /// 第二行\n    #[deprecated(note = \"x\")]\n    A = 1,\n    /// 乙\n    B = 2,\n}\n";
+        let rows = vec![Enum {
+            name: String::from("B_NEW"),
+            variant: String::from("BNew"),
+            code: 2,
+            message: String::from("乙2"),
+            attrs: vec![],
+        }];
+        let merged = merge_rows(existing, rows, EnumStyle::Native);
+        let merged: Vec<_> = merged
+            .iter()
+            .map(|e| {
+                (
+                    e.variant.as_str(),
+                    e.code,
+                    e.message.as_str(),
+                    e.attrs.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            merged,
+            [
+                (
+                    "A",
+                    1,
+                    "甲\n第二行",
+                    vec![String::from("deprecated(note = \"x\")")]
+                ),
+                ("BNew", 2, "乙2", vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn const_names_collide_after_case_conversion() {
+        let dir = scratch("dup-name");
+        let err = EnumGen::new()
+            .out(format!("{}/enums", dir))
+            .name("-e=d -f=d:a-1-x,A-2-y,b-3-z")
+            .run()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("{}/enums/d/mod.rs: d::A 重复定义", dir)
+        );
+    }
+
+    #[test]
+    fn const_codes_collide() {
+        let dir = scratch("dup-code");
+        let err = EnumGen::new()
+            .out(format!("{}/enums", dir))
+            .name("-e=d -f=d:a-1-x,b-1-y")
+            .run()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("{}/enums/d/mod.rs: d::B 与 d::A 的 code 重复: 1", dir)
+        );
+    }
+
+    #[test]
+    fn code_names_collide_from_sql() {
+        let dir = scratch("dup-sql");
+        let sql = format!("{}/schema.sql", dir);
+        std::fs::write(
+            &sql,
+            "CREATE TABLE t (type tinyint COMMENT '类型:1-a,1-b');",
+        )
+        .unwrap();
+        for style in [EnumStyle::Const, EnumStyle::Native] {
+            let err = EnumGen::new()
+                .out(format!("{}/enums", dir))
+                .from_sql(sql.as_str())
+                .style(style)
+                .run()
+                .unwrap_err();
+            let ident = match style {
+                EnumStyle::Const => "t_type::CODE_1",
+                EnumStyle::Native => "TType::Code1",
+            };
+            assert_eq!(
+                err.to_string(),
+                format!("{}/enums/t_type/mod.rs: {} 重复定义", dir, ident)
+            );
+        }
+    }
+
+    #[test]
+    fn check_ignores_hand_written_tail() {
+        let dir = scratch("check-tail");
+        let out = format!("{}/enums", dir);
+        let generate = |name: &str| {
+            EnumGen::new()
+                .out(out.as_str())
+                .name(name)
+                .check(true)
+                .run()
+        };
+        EnumGen::new()
+            .out(out.as_str())
+            .name("-e=yn -f=是否:yes-1-是")
+            .run()
+            .unwrap();
+        let path = format!("{}/yn/mod.rs", out);
+        let tail = "\nimpl Yn {}\n";
+        let content = std::fs::read_to_string(&path).unwrap() + tail;
+        std::fs::write(&path, &content).unwrap();
+        assert!(generate("-e=yn -f=是否:yes-1-是").is_ok());
+        assert!(matches!(
+            generate("-e=yn -f=是否:yes-2-是"),
+            Err(Error::Stale { .. })
+        ));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+
+        std::fs::write(&path, "pub const YES: i32 = 1;\n").unwrap();
+        assert!(matches!(
+            generate("-e=yn -f=是否:yes-1-是"),
+            Err(Error::Exists { .. })
+        ));
+    }
 }
@@ -1,3 +1,6 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Enum {
     #[allow(dead_code)]
     Body { code: i32, message: &'static str },
@@ -7,14 +10,30 @@ impl Enum {
     #[allow(dead_code)]
     pub fn code(&self) -> i32 {
         match &self {
-            Enum::Body { code, message: _ } => *code
+            Enum::Body { code, message: _ } => *code,
         }
     }
 
     #[allow(dead_code)]
     pub fn message(&self) -> &'static str {
         match &self {
-            Enum::Body { code: _, message } => *message
+            Enum::Body { code: _, message } => message,
         }
     }
-}
\ No newline at end of file
+
+    /// 在枚举值列表中按 code 查找
+    #[allow(dead_code)]
+    pub fn find(entries: &[Enum], code: i32) -> Option<Enum> {
+        entries.iter().find(|e| e.code() == code).copied()
+    }
+}
+
+/// 序列化为 `{"code": 1, "message": "..."}`, 用于接口返回
+impl Serialize for Enum {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Enum", 2)?;
+        state.serialize_field("code", &self.code())?;
+        state.serialize_field("message", self.message())?;
+        state.end()
+    }
+}
@@ -0,0 +1,125 @@
+use std::fmt;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+///
+/// 生成器错误
+///
+#[derive(Debug)]
+pub enum Error {
+    /// 文件读写失败
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    /// YAML 解析失败, 行列号见 `source`
+    Yaml {
+        path: String,
+        source: serde_yml::Error,
+    },
+    /// 输入语法错误 (enum DSL, YAML 结构等), `line` 为 0 表示无行号
+    Syntax {
+        path: String,
+        line: usize,
+        message: String,
+    },
+    /// 模板渲染失败
+    Template {
+        name: String,
+        source: minijinja::Error,
+    },
+    /// Rust 源码解析失败
+    RustParse { path: String, source: syn::Error },
+    /// 错误码或常量名重复
+    DuplicateCode { duplicates: Vec<Duplicate> },
+    /// 输出文件已存在 (`--on-exists=error`)
+    Exists { path: String },
+    /// `--check` 时生成结果与已有文件不一致
+    Stale { paths: Vec<String> },
+    /// rustfmt 执行失败
+    Rustfmt { path: String, message: String },
+}
+
+///
+/// 重复定义, `first`/`second` 为两处定义的位置
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplicate {
+    pub what: String,
+    pub first: String,
+    pub second: String,
+}
+
+impl Error {
+    pub(crate) fn io(path: &str, source: std::io::Error) -> Error {
+        Error::Io {
+            path: path.to_string(),
+            source,
+        }
+    }
+
+    pub(crate) fn syntax(path: &str, line: usize, message: impl Into<String>) -> Error {
+        Error::Syntax {
+            path: path.to_string(),
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path, source),
+            Error::Yaml { path, source } => write!(f, "{}: 解析YAML失败: {}", path, source),
+            Error::Syntax {
+                path,
+                line: 0,
+                message,
+            } => write!(f, "{}: {}", path, message),
+            Error::Syntax {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path, line, message),
+            Error::Template { name, source } => {
+                write!(f, "{}: 渲染模板失败: {:#}", name, source)
+            }
+            Error::RustParse { path, source } => {
+                let start = source.span().start();
+                write!(
+                    f,
+                    "{}:{}:{}: 解析Rust代码失败: {}",
+                    path,
+                    start.line,
+                    start.column + 1,
+                    source
+                )
+            }
+            Error::DuplicateCode { duplicates } => {
+                write!(f, "发现{}处重复定义:", duplicates.len())?;
+                for d in duplicates {
+                    write!(f, "\n  {}: {} 与 {}", d.what, d.first, d.second)?;
+                }
+                Ok(())
+            }
+            Error::Exists { path } => write!(f, "{}: 文件已存在", path),
+            Error::Stale { paths } => {
+                write!(f, "生成结果与已有文件不一致: {}", paths.join(", "))
+            }
+            Error::Rustfmt { path, message } => write!(f, "{}: rustfmt失败: {}", path, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Yaml { source, .. } => Some(source),
+            Error::Template { source, .. } => Some(source),
+            Error::RustParse { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -1,45 +1,23 @@
-use std::fs::OpenOptions;
-use std::io::Write;
+use crate::cmd::error::{Error, Result};
 
 #[allow(dead_code)]
-pub fn create_file(file_path: &str) {
-    // 检查文件是否存在
-    if std::path::Path::new(file_path).exists() {
-        println!("文件已存在：{}", file_path);
-    } else {
-        // 文件不存在，尝试创建文件
-        match std::fs::File::create(file_path) {
-            Ok(_) => println!("文件创建成功：{}", file_path),
-            Err(e) => println!("创建文件失败：{}", e),
-        }
+pub fn create_dir(dir: &str) -> Result<()> {
+    if !std::path::Path::new(dir).exists() {
+        std::fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
     }
+    Ok(())
 }
 
 #[allow(dead_code)]
-pub fn create_and_append(file_path: &str, content: &str) {
-    // 创建文件
-    create_file(file_path);
-
-    // 打开文件并追加内容
-    match OpenOptions::new().append(true).open(file_path) {
-        Ok(mut file) => {
-            // 追加内容
-            if let Err(e) = writeln!(file, "{}", content) {
-                println!("追加内容失败：{}", e);
-            }
-        }
-        Err(e) => println!("打开文件失败：{}", e),
-    }
+pub fn read_file(file_path: &str) -> Result<String> {
+    std::fs::read_to_string(file_path).map_err(|e| Error::io(file_path, e))
 }
 
-#[allow(dead_code)]
-pub fn write_file(file_path: &str, content: &str) {
-    match OpenOptions::new().write(true).open(file_path) {
-        Ok(mut file) => {
-            if let Err(e) = writeln!(file, "{}", content) {
-                println!("写文件内容失败：{}", e);
-            }
-        }
-        Err(e) => println!("打开文件失败：{}", e),
-    }
-}
\ No newline at end of file
+// 测试用的空目录, 位于系统临时目录下, 每次运行前清空
+#[cfg(test)]
+pub(crate) fn scratch(name: &str) -> String {
+    let dir = std::env::temp_dir().join(format!("hirust-gen-test-{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    create_dir(dir.display().to_string().as_str()).unwrap();
+    dir.display().to_string()
+}
@@ -1,4 +1,6 @@
-use crate::cmd::{code, controller, r#enum};
+use crate::cmd::error::Result;
+use crate::cmd::plan::OnExists;
+use crate::cmd::{code, controller, r#enum, stub};
 use clap::{ArgMatches, Command};
 
 ///
@@ -7,14 +9,42 @@ use clap::{ArgMatches, Command};
 /// Examples
 ///```text
 /// cargo run gen code --out="./errcode/mod.rs" --source="./yaml"
+/// cargo run gen code --out="./errcode/mod.rs" --source="./yaml" --dry-run
+/// cargo run gen code --out="./errcode/mod.rs" --source="./yaml" --check
+/// cargo run gen code --out="./errcode/mod.rs" --source="./yaml" --lock="./errcode.lock"
+/// cargo run gen code --out="./errcode/mod.rs" --source="./yaml" --default-locale="zh-CN"
+/// cargo run gen code --out="./errcode/mod.rs" --source="./yaml" --style=enum
+/// cargo run gen code --out="./errcode/mod.rs" --source="./yaml" --error-code-path="hirust_resp::ErrorCode"
+/// cargo run gen code --source="./yaml" --out="./errcode/mod.rs" --out="./docs/errcode.md" --out="./web/errcode.ts" --out="./openapi.json" --format=rust --format=md --format=ts --format=openapi
+/// cargo run gen code import --from="./src/errcode/mod.rs" --out="./yaml"
 /// cargo run gen enum --out="./enums" --name="-e=order_flow -f=发起订单:selling_assistant-1-销售内勤,sale-2-销售"
 /// cargo run gen enum --out="./enums" --file="./src/cmd/enum.md"
+/// cargo run gen enum --out="./enums" --file="./enums.yaml"
+/// cargo run gen enum --out="./enums" --file="./enums.yaml" --style=native --serde=name
+/// cargo run gen enum --out="./enums" --file="./enums.yaml" --style=native --db=sqlx
+/// cargo run gen enum --out="./enums" --from-sql="./schema.sql"
+/// cargo run gen enum --out="./enums" --file="./src/cmd/enum.md" --on-exists=merge
 /// cargo run gen controller --out="./src/app/controllers" --name="test"
 /// cargo run gen controller --out="./src/app/controllers" --name="test" --tag="app::controllers::test"
+/// cargo run gen controller --out="./src/app/controllers" --name="test" --templates="./stubs"
+/// cargo run gen stubs publish --out="./stubs"
 ///```
 ///
+/// 出错时打印错误并以非零状态码退出, 需要自行处理错误时使用 [`try_command`]
+///
 #[allow(dead_code)]
 pub fn command() -> ArgMatches {
+    match try_command() {
+        Ok(matches) => matches,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub fn try_command() -> Result<ArgMatches> {
     let matches = Command::new("pacman")
         .about("package manager utility")
         .version("5.2.1")
@@ -27,35 +57,27 @@ pub fn command() -> ArgMatches {
                 .arg_required_else_help(true)
                 .subcommand(code::command())
                 .subcommand(controller::command())
-                .subcommand(r#enum::command()),
+                .subcommand(r#enum::command())
+                .subcommand(stub::command()),
         )
         .get_matches();
 
-    match matches.subcommand() {
-        Some(("gen", gen_matches)) => {
-            match gen_matches.subcommand_matches("code") {
-                Some(arg_matches) => {
-                    code::execute(arg_matches);
-                }
-                _ => {}
-            }
-            match gen_matches.subcommand_matches("enum") {
-                Some(arg_matches) => {
-                    r#enum::execute(arg_matches);
-                }
-                _ => {}
-            }
-            match gen_matches.subcommand_matches("controller") {
-                Some(arg_matches) => {
-                    controller::execute(arg_matches);
-                }
-                _ => {}
-            }
+    if let Some(("gen", gen_matches)) = matches.subcommand() {
+        if let Some(arg_matches) = gen_matches.subcommand_matches("code") {
+            code::execute(arg_matches)?;
+        }
+        if let Some(arg_matches) = gen_matches.subcommand_matches("enum") {
+            r#enum::execute(arg_matches)?;
+        }
+        if let Some(arg_matches) = gen_matches.subcommand_matches("controller") {
+            controller::execute(arg_matches)?;
+        }
+        if let Some(arg_matches) = gen_matches.subcommand_matches("stubs") {
+            stub::execute(arg_matches)?;
         }
-        _ => {} // If all subcommands are defined above, anything else is unreachable
     }
 
-    matches
+    Ok(matches)
 }
 
 #[allow(dead_code)]
@@ -92,4 +114,29 @@ pub(crate) fn tag(arg_matches: &ArgMatches) -> String {
         tag = out.map(|s| s.as_str()).collect::<Vec<_>>().join("");
     }
     tag
-}
\ No newline at end of file
+}
+
+#[allow(dead_code)]
+pub(crate) fn templates(arg_matches: &ArgMatches) -> String {
+    let mut templates = String::new();
+    if let Some(out) = arg_matches.get_many::<String>("templates") {
+        templates = out.map(|s| s.as_str()).collect::<Vec<_>>().join("");
+    }
+    templates
+}
+
+#[allow(dead_code)]
+pub(crate) fn on_exists(arg_matches: &ArgMatches) -> Option<OnExists> {
+    arg_matches
+        .get_one::<String>("on-exists")
+        .and_then(|s| s.parse().ok())
+}
+
+#[allow(dead_code)]
+pub(crate) fn lock(arg_matches: &ArgMatches) -> String {
+    let mut lock = String::new();
+    if let Some(out) = arg_matches.get_many::<String>("lock") {
+        lock = out.map(|s| s.as_str()).collect::<Vec<_>>().join("");
+    }
+    lock
+}
@@ -0,0 +1,134 @@
+use heck::{ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
+
+// Rust 关键字 (含保留字), 作为标识符时需要写成 r#xxx
+const KEYWORDS: [&str; 52] = [
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "async", "await", "dyn", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "typeof", "unsized", "virtual", "yield", "try", "gen",
+];
+
+// 不能写成原始标识符的关键字
+const NON_RAW: [&str; 4] = ["crate", "self", "Self", "super"];
+
+///
+/// 转换为 SCREAMING_SNAKE_CASE 常量名, 如 `selling-assistant` -> `SELLING_ASSISTANT`
+///
+pub(crate) fn constant(name: &str) -> Result<String, String> {
+    check(name)?;
+    escape(name, name.to_shouty_snake_case())
+}
+
+///
+/// 转换为 UpperCamelCase 变体名, 如 `not_found` -> `NotFound`
+///
+pub(crate) fn variant(name: &str) -> Result<String, String> {
+    check(name)?;
+    escape(name, name.to_upper_camel_case())
+}
+
+///
+/// 转换为 snake_case 模块名或字段名, 如 `OrderFlow` -> `order_flow`, 关键字转为 `r#type`
+///
+pub(crate) fn snake(name: &str) -> Result<String, String> {
+    check(name)?;
+    escape(name, name.to_snake_case())
+}
+
+/// 去掉原始标识符前缀, 用于文件路径
+pub(crate) fn unraw(ident: &str) -> &str {
+    ident.strip_prefix("r#").unwrap_or(ident)
+}
+
+// 只允许 ASCII 字母、数字、下划线和连字符, 不能以数字开头
+fn check(name: &str) -> Result<(), String> {
+    let Some(first) = name.chars().next() else {
+        return Err(String::from("标识符不能为空"));
+    };
+    if let Some(c) = name
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || *c == '_' || *c == '-'))
+    {
+        return Err(format!(
+            "{} 不是合法的标识符: 包含字符 {:?}, 只能使用 ASCII 字母、数字、_ 和 -",
+            name, c
+        ));
+    }
+    if first.is_ascii_digit() {
+        return Err(format!("{} 不是合法的标识符: 不能以数字开头", name));
+    }
+    if !name.chars().any(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!("{} 不是合法的标识符: 至少需要一个字母或数字", name));
+    }
+    Ok(())
+}
+
+fn escape(name: &str, ident: String) -> Result<String, String> {
+    if NON_RAW.contains(&ident.as_str()) {
+        return Err(format!("{} 转换后为关键字 {}, 不能作为标识符", name, ident));
+    }
+    if KEYWORDS.contains(&ident.as_str()) {
+        return Ok(format!("r#{}", ident));
+    }
+    Ok(ident)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn case_conversion() {
+        assert_eq!(constant("selling-assistant").unwrap(), "SELLING_ASSISTANT");
+        assert_eq!(constant("code_1").unwrap(), "CODE_1");
+        assert_eq!(variant("not_found").unwrap(), "NotFound");
+        assert_eq!(variant("code_n1").unwrap(), "CodeN1");
+        assert_eq!(snake("OrderFlow").unwrap(), "order_flow");
+        assert_eq!(snake("order-flow").unwrap(), "order_flow");
+    }
+
+    #[test]
+    fn keywords_are_escaped() {
+        assert_eq!(snake("type").unwrap(), "r#type");
+        assert_eq!(snake("Match").unwrap(), "r#match");
+        assert_eq!(variant("type").unwrap(), "Type");
+        assert_eq!(constant("type").unwrap(), "TYPE");
+        assert_eq!(unraw("r#type"), "type");
+        assert_eq!(unraw("order_flow"), "order_flow");
+    }
+
+    #[test]
+    fn keywords_without_raw_form() {
+        assert_eq!(
+            snake("self").unwrap_err(),
+            "self 转换后为关键字 self, 不能作为标识符"
+        );
+        assert_eq!(
+            variant("self").unwrap_err(),
+            "self 转换后为关键字 Self, 不能作为标识符"
+        );
+        assert_eq!(constant("self").unwrap(), "SELF");
+    }
+
+    #[test]
+    fn invalid_names() {
+        assert_eq!(
+            constant("1st").unwrap_err(),
+            "1st 不是合法的标识符: 不能以数字开头"
+        );
+        assert_eq!(
+            variant("未找到").unwrap_err(),
+            "未找到 不是合法的标识符: 包含字符 '未', 只能使用 ASCII 字母、数字、_ 和 -"
+        );
+        assert_eq!(
+            snake("café").unwrap_err(),
+            "café 不是合法的标识符: 包含字符 'é', 只能使用 ASCII 字母、数字、_ 和 -"
+        );
+        assert_eq!(
+            constant("--").unwrap_err(),
+            "-- 不是合法的标识符: 至少需要一个字母或数字"
+        );
+        assert_eq!(constant("").unwrap_err(), "标识符不能为空");
+    }
+}
@@ -0,0 +1,688 @@
+use crate::cmd::error::{Error, Result};
+use crate::cmd::plan::{OnExists, Plan};
+use crate::cmd::report::Report;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use quote::quote;
+use serde_yml::{Mapping, Value};
+use std::path::Path;
+use syn::punctuated::Punctuated;
+use syn::{
+    Attribute, BinOp, Block, Expr, File, ImplItem, Item, Lit, Member, Meta, Pat, Stmt, Token, Type,
+    UnOp, UseTree, parse_file,
+};
+
+// cargo run gen code import --from="./src/errcode/mod.rs" --out="./yaml"
+#[allow(dead_code)]
+pub(crate) fn command() -> Command {
+    Command::new("import")
+        .about("Import ErrorCode constants from a Rust module into YAML.")
+        .arg(
+            Arg::new("from")
+                .required(true)
+                .long("from")
+                .help("Rust module with ErrorCode constants"),
+        )
+        .arg(
+            Arg::new("out")
+                .required(true)
+                .short('o')
+                .long("out")
+                .action(ArgAction::Set)
+                .help("Output Directory"),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .action(ArgAction::SetTrue)
+                .help("Print a diff instead of writing files"),
+        )
+        .arg(
+            Arg::new("on-exists")
+                .long("on-exists")
+                .value_parser(["skip", "overwrite", "error"])
+                .help("Policy for existing output files"),
+        )
+}
+
+#[allow(dead_code)]
+pub(crate) fn execute(arg_matches: &ArgMatches) -> Result<()> {
+    let mut code_import = CodeImport::new()
+        .from(
+            arg_matches
+                .get_one::<String>("from")
+                .cloned()
+                .unwrap_or_default(),
+        )
+        .out(crate::cmd::r#gen::out_file(arg_matches))
+        .dry_run(arg_matches.get_flag("dry-run"));
+    if let Some(on_exists) = crate::cmd::r#gen::on_exists(arg_matches) {
+        code_import = code_import.on_exists(on_exists);
+    }
+    code_import.run()?;
+    Ok(())
+}
+
+// 从 Rust 模块中提取的错误码常量
+#[derive(Default)]
+pub(crate) struct Imported {
+    pub name: String,
+    pub code: i64,
+    pub message: String,
+    /// ErrorCodeExt::message_for 中的 locale -> message, 按分支顺序
+    pub messages: Vec<(String, String)>,
+    /// 以下取自 ErrorCodeMeta 的实现
+    pub http_status: Option<u16>,
+    pub level: Option<String>,
+    pub retryable: Option<bool>,
+    /// `#[deprecated]` 的说明, 没有说明时为空字符串
+    pub deprecated: Option<String>,
+}
+
+///
+/// 从手写的错误码模块导入 YAML, 生成的 YAML 经 `gen code` 可得到相同的常量
+///
+/// Examples
+///```no_run
+/// let report = hirust_gen::CodeImport::new()
+///     .from("./src/errcode/mod.rs")
+///     .out("./yaml")
+///     .run()?;
+/// # Ok::<(), hirust_gen::Error>(())
+///```
+///
+#[derive(Debug, Clone, Default)]
+pub struct CodeImport {
+    from: String,
+    out: String,
+    dry_run: bool,
+    on_exists: Option<OnExists>,
+}
+
+impl CodeImport {
+    pub fn new() -> Self {
+        CodeImport::default()
+    }
+
+    /// 错误码模块文件
+    pub fn from(mut self, from: impl Into<String>) -> Self {
+        self.from = from.into();
+        self
+    }
+
+    /// YAML 输出目录
+    pub fn out(mut self, out: impl Into<String>) -> Self {
+        self.out = out.into();
+        self
+    }
+
+    /// 只输出 diff, 不写文件
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// 输出文件已存在时的处理策略, 默认 `OnExists::Skip`
+    pub fn on_exists(mut self, on_exists: OnExists) -> Self {
+        self.on_exists = Some(on_exists);
+        self
+    }
+
+    pub fn run(&self) -> Result<Report> {
+        let path = self.from.as_str();
+        let content = crate::cmd::file::read_file(path)?;
+        let syntax: File = parse_file(&content).map_err(|source| Error::RustParse {
+            path: path.to_string(),
+            source,
+        })?;
+
+        let (mut rows, constructor) = constants(path, &syntax)?;
+        if rows.is_empty() {
+            return Err(Error::syntax(path, 0, "没有找到 ErrorCode 常量"));
+        }
+        let default_locale = metadata(path, &syntax, &mut rows)?;
+
+        let module = module_name(path);
+        let out_file = format!("{}/{}.yaml", self.out.trim_end_matches('/'), module);
+        let yaml = serde_yml::to_string(&Value::Mapping(to_yaml(&rows, module.as_str()))).map_err(
+            |source| Error::Yaml {
+                path: out_file.clone(),
+                source,
+            },
+        )?;
+
+        let mut plan = Plan::default();
+        plan.write_with(
+            out_file.as_str(),
+            yaml.trim_end(),
+            self.on_exists.unwrap_or(OnExists::Skip),
+        )?;
+        let report = plan.commit(self.dry_run, false)?;
+
+        // 生成相同模块所需的参数
+        if let Some(error_code_path) = error_code_path(&syntax) {
+            println!("--error-code-path=\"{}\"", error_code_path);
+        }
+        if let Some(constructor) = constructor {
+            println!("--error-code-constructor=\"{}\"", constructor);
+        }
+        if let Some(default_locale) = default_locale {
+            println!("--default-locale=\"{}\"", default_locale);
+        }
+        Ok(report)
+    }
+}
+
+/// 提取模块中的 ErrorCode 常量, 同时返回 `ErrorCode::new(..)` 形式的构造表达式
+pub(crate) fn constants(path: &str, syntax: &File) -> Result<(Vec<Imported>, Option<String>)> {
+    let mut rows: Vec<Imported> = vec![];
+    let mut constructor: Option<String> = None;
+    for item in &syntax.items {
+        let Item::Const(item) = item else {
+            continue;
+        };
+        if !is_error_code(&item.ty) {
+            continue;
+        }
+        let name = item.ident.to_string();
+        let line = item.ident.span().start().line;
+        let (code, message) = match &*item.expr {
+            // ErrorCode { code: 10000, message: "..." }
+            Expr::Struct(expr) => {
+                let field = |member: &str| {
+                    expr.fields
+                        .iter()
+                        .find(|f| matches!(&f.member, Member::Named(ident) if ident == member))
+                        .map(|f| &f.expr)
+                };
+                (
+                    field("code").and_then(int),
+                    field("message").and_then(string),
+                )
+            }
+            // ErrorCode::new(10000, "...")
+            Expr::Call(expr) if expr.args.len() == 2 => {
+                let func = &expr.func;
+                constructor.get_or_insert_with(|| {
+                    format!(
+                        "{}({{code}}, {{message}})",
+                        quote!(#func).to_string().replace(' ', "")
+                    )
+                });
+                (int(&expr.args[0]), string(&expr.args[1]))
+            }
+            _ => (None, None),
+        };
+        let (Some(code), Some(message)) = (code, message) else {
+            return Err(Error::syntax(
+                path,
+                line,
+                format!("无法识别 {} 的错误码和 message", name),
+            ));
+        };
+        rows.push(Imported {
+            deprecated: deprecated(path, line, &name, &item.attrs)?,
+            name,
+            code,
+            message,
+            ..Imported::default()
+        });
+    }
+    Ok((rows, constructor))
+}
+
+// 常量上的 #[deprecated], 生成代码中的 #[allow(..)] 和文档注释忽略, 其他属性无法导入
+fn deprecated(path: &str, line: usize, name: &str, attrs: &[Attribute]) -> Result<Option<String>> {
+    let mut deprecated = None;
+    for attr in attrs {
+        let note = match &attr.meta {
+            _ if attr.path().is_ident("allow") || attr.path().is_ident("doc") => continue,
+            // #[deprecated]
+            Meta::Path(meta) if meta.is_ident("deprecated") => Some(String::new()),
+            // #[deprecated = "..."]
+            Meta::NameValue(meta) if meta.path.is_ident("deprecated") => string(&meta.value),
+            // #[deprecated(note = "...")]
+            Meta::List(meta) if meta.path.is_ident("deprecated") => meta
+                .parse_args_with(Punctuated::<syn::MetaNameValue, Token![,]>::parse_terminated)
+                .ok()
+                .map(|args| {
+                    args.iter()
+                        .find(|arg| arg.path.is_ident("note"))
+                        .and_then(|arg| string(&arg.value))
+                        .unwrap_or_default()
+                }),
+            _ => None,
+        };
+        let Some(note) = note else {
+            let attr = &attr.meta;
+            return Err(Error::syntax(
+                path,
+                line,
+                format!("无法导入 {} 上的属性 #[{}]", name, quote!(#attr)),
+            ));
+        };
+        deprecated = Some(note);
+    }
+    Ok(deprecated)
+}
+
+// 读取 gen code 生成的 ErrorCodeMeta 和 ErrorCodeExt 实现, 补充到对应的错误码,
+// 返回常量 message 使用的 locale (不是第一个 locale 时)
+fn metadata(path: &str, syntax: &File, rows: &mut [Imported]) -> Result<Option<String>> {
+    let unknown = |code: i64| {
+        Error::syntax(
+            path,
+            0,
+            format!(
+                "ErrorCodeMeta/ErrorCodeExt 中的错误码 {} 没有对应的常量",
+                code
+            ),
+        )
+    };
+    for item in &syntax.items {
+        let Item::Impl(item) = item else {
+            continue;
+        };
+        let Some((_, trait_path, _)) = &item.trait_ else {
+            continue;
+        };
+        let Some(trait_name) = trait_path.segments.last().map(|s| s.ident.to_string()) else {
+            continue;
+        };
+        if trait_name != "ErrorCodeMeta" && trait_name != "ErrorCodeExt" {
+            continue;
+        }
+        for impl_item in &item.items {
+            let ImplItem::Fn(function) = impl_item else {
+                continue;
+            };
+            let function_name = function.sig.ident.to_string();
+            let line = function.sig.ident.span().start().line;
+            let unsupported = || {
+                Error::syntax(
+                    path,
+                    line,
+                    format!("无法识别 {}::{} 的实现", trait_name, function_name),
+                )
+            };
+            match function_name.as_str() {
+                "http_status" => {
+                    for (pattern, body) in arms(&function.block) {
+                        let code = pattern.first().and_then(int).ok_or_else(unsupported)?;
+                        let status = int(body)
+                            .and_then(|s| u16::try_from(s).ok())
+                            .ok_or_else(unsupported)?;
+                        let row = rows
+                            .iter_mut()
+                            .find(|r| r.code == code)
+                            .ok_or_else(|| unknown(code))?;
+                        row.http_status = Some(status);
+                    }
+                }
+                "level" | "retryable" => {
+                    for code in matched(&function.block).ok_or_else(unsupported)? {
+                        let row = rows
+                            .iter_mut()
+                            .find(|r| r.code == code)
+                            .ok_or_else(|| unknown(code))?;
+                        if function_name == "level" {
+                            row.level = Some(String::from("warn"));
+                        } else {
+                            row.retryable = Some(true);
+                        }
+                    }
+                }
+                "message_for" => {
+                    for (pattern, body) in arms(&function.block) {
+                        let (Some(code), Some(locale), Some(message)) = (
+                            pattern.first().and_then(int),
+                            pattern.get(1).and_then(string),
+                            string(body),
+                        ) else {
+                            return Err(unsupported());
+                        };
+                        let row = rows
+                            .iter_mut()
+                            .find(|r| r.code == code)
+                            .ok_or_else(|| unknown(code))?;
+                        row.messages.push((locale, message));
+                    }
+                }
+                _ => return Err(unsupported()),
+            }
+        }
+    }
+
+    // gen code 取 --default-locale 的翻译作为常量的 message, 没有该翻译或未指定时取第一个
+    let translated: Vec<&Imported> = rows.iter().filter(|r| !r.messages.is_empty()).collect();
+    let uses = |default_locale: &str| {
+        translated.iter().all(|row| {
+            let message = row
+                .messages
+                .iter()
+                .find(|(locale, _)| locale == default_locale)
+                .or(row.messages.first())
+                .map(|(_, message)| message);
+            message == Some(&row.message)
+        })
+    };
+    if uses("") {
+        return Ok(None);
+    }
+    let locales = translated
+        .iter()
+        .flat_map(|row| row.messages.iter().map(|(l, _)| l));
+    for locale in locales {
+        if uses(locale) {
+            return Ok(Some(locale.clone()));
+        }
+    }
+    Err(Error::syntax(
+        path,
+        0,
+        "常量的 message 与 message_for 的翻译不对应, 无法确定 --default-locale",
+    ))
+}
+
+// 函数体中 match 的分支, 返回 (模式中的字面量, 分支表达式), 通配分支忽略
+fn arms(block: &Block) -> Vec<(Vec<Expr>, &Expr)> {
+    let Some(Stmt::Expr(Expr::Match(expr), None)) = block.stmts.last() else {
+        return vec![];
+    };
+    expr.arms
+        .iter()
+        .filter_map(|arm| {
+            let pattern = match &arm.pat {
+                Pat::Wild(_) => return None,
+                Pat::Tuple(tuple) => tuple.elems.iter().map(pattern).collect::<Option<_>>(),
+                pat => pattern(pat).map(|p| vec![p]),
+            };
+            Some((pattern.unwrap_or_default(), &*arm.body))
+        })
+        .collect()
+}
+
+// 字面量模式, 负数按表达式解析
+fn pattern(pat: &Pat) -> Option<Expr> {
+    syn::parse2(quote!(#pat)).ok()
+}
+
+// 函数体中 matches!(self.code, a | b) 的错误码, 没有 matches! 时为空
+fn matched(block: &Block) -> Option<Vec<i64>> {
+    let expr = match block.stmts.last() {
+        Some(Stmt::Expr(Expr::If(expr), None)) => &*expr.cond,
+        Some(Stmt::Expr(expr, None)) => expr,
+        _ => return None,
+    };
+    match expr {
+        Expr::Macro(expr) if expr.mac.path.is_ident("matches") => {
+            let args = expr
+                .mac
+                .parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+                .ok()?;
+            let mut codes = vec![];
+            alternatives(args.iter().nth(1)?, &mut codes)?;
+            Some(codes)
+        }
+        _ => Some(vec![]),
+    }
+}
+
+// a | b | c 中的错误码
+fn alternatives(expr: &Expr, codes: &mut Vec<i64>) -> Option<()> {
+    match expr {
+        Expr::Binary(expr) if matches!(expr.op, BinOp::BitOr(_)) => {
+            alternatives(&expr.left, codes)?;
+            alternatives(&expr.right, codes)
+        }
+        expr => {
+            codes.push(int(expr)?);
+            Some(())
+        }
+    }
+}
+
+// 按声明顺序输出, base 取最小错误码, 与上一个错误码连续时省略 code
+fn to_yaml(rows: &[Imported], module: &str) -> Mapping {
+    let base = rows.iter().map(|r| r.code).min().unwrap_or_default();
+    let mut meta = Mapping::new();
+    meta.insert(Value::from("base"), Value::from(base));
+    meta.insert(Value::from("module"), Value::from(module));
+
+    let mut root = Mapping::new();
+    root.insert(Value::from("_meta"), Value::Mapping(meta));
+    let mut iota = base;
+    for row in rows {
+        let key = Value::from(row.name.to_lowercase());
+        let message = if row.messages.is_empty() {
+            Value::from(row.message.as_str())
+        } else {
+            let mut messages = Mapping::new();
+            for (locale, message) in &row.messages {
+                messages.insert(Value::from(locale.as_str()), Value::from(message.as_str()));
+            }
+            Value::Mapping(messages)
+        };
+        let mut entry = Mapping::new();
+        if row.code != iota {
+            entry.insert(Value::from("code"), Value::from(row.code));
+        }
+        entry.insert(Value::from("message"), message.clone());
+        if let Some(http_status) = row.http_status {
+            entry.insert(Value::from("http_status"), Value::from(http_status));
+        }
+        if let Some(level) = &row.level {
+            entry.insert(Value::from("level"), Value::from(level.as_str()));
+        }
+        if let Some(retryable) = row.retryable {
+            entry.insert(Value::from("retryable"), Value::from(retryable));
+        }
+        match row.deprecated.as_deref() {
+            Some("") => entry.insert(Value::from("deprecated"), Value::from(true)),
+            Some(note) => entry.insert(Value::from("deprecated"), Value::from(note)),
+            None => None,
+        };
+        // 只有 message 时使用简写
+        let value = if entry.len() == 1 && message.is_string() {
+            message
+        } else {
+            Value::Mapping(entry)
+        };
+        root.insert(key, value);
+        iota = row.code + 1;
+    }
+    root
+}
+
+fn is_error_code(ty: &Type) -> bool {
+    match ty {
+        Type::Path(ty) => ty
+            .path
+            .segments
+            .last()
+            .is_some_and(|s| s.ident == "ErrorCode"),
+        _ => false,
+    }
+}
+
+pub(crate) fn int(expr: &Expr) -> Option<i64> {
+    match expr {
+        Expr::Lit(expr) => match &expr.lit {
+            Lit::Int(lit) => lit.base10_parse().ok(),
+            _ => None,
+        },
+        Expr::Unary(expr) if matches!(expr.op, UnOp::Neg(_)) => int(&expr.expr).map(|i| -i),
+        _ => None,
+    }
+}
+
+fn string(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(expr) => match &expr.lit {
+            Lit::Str(lit) => Some(lit.value()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// use xxx::ErrorCode; 的导入路径
+fn error_code_path(syntax: &File) -> Option<String> {
+    syntax.items.iter().find_map(|item| match item {
+        Item::Use(item) => {
+            let tree: &UseTree = &item.tree;
+            let path = quote!(#tree).to_string().replace(' ', "");
+            path.ends_with("::ErrorCode").then_some(path)
+        }
+        _ => None,
+    })
+}
+
+// mod.rs 取目录名, 其他取文件名
+fn module_name(path: &str) -> String {
+    let path = Path::new(path);
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    if stem == "mod"
+        && let Some(dir) = path
+            .parent()
+            .and_then(|p| p.file_name())
+            .and_then(|s| s.to_str())
+    {
+        return dir.to_string();
+    }
+    stem.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::code::CodeGen;
+    use crate::cmd::file::scratch;
+
+    const SOURCE: &str = r#"_meta: {base: 10000, module: user}
+not_found:
+  message: {en: "User \"x\" not found", zh-CN: 用户不存在}
+  http_status: 404
+disabled:
+  message: 用户已禁用
+  level: warn
+  deprecated: 使用 USER_LOCKED
+locked:
+  code: 10005
+  message: 'a: b # c'
+  retryable: true
+  deprecated: true
+login:
+  failed: 登录失败
+"#;
+
+    // YAML -> gen code -> import -> gen code, 两次生成的模块应完全相同
+    #[test]
+    fn import_round_trip() {
+        let dir = scratch("import-round-trip");
+        std::fs::create_dir_all(format!("{}/yaml", dir)).unwrap();
+        std::fs::write(format!("{}/yaml/user.yaml", dir), SOURCE).unwrap();
+        let generate = |source: &str, out: &str| {
+            CodeGen::new()
+                .source(format!("{}/{}", dir, source))
+                .out(format!("{}/{}", dir, out))
+                .default_locale("zh-CN")
+                .run()
+                .unwrap();
+            std::fs::read_to_string(format!("{}/{}", dir, out)).unwrap()
+        };
+        let first = generate("yaml", "user/mod.rs");
+        assert!(first.contains("#[deprecated(note = \"使用 USER_LOCKED\")]"));
+
+        CodeImport::new()
+            .from(format!("{}/user/mod.rs", dir))
+            .out(format!("{}/imported", dir))
+            .run()
+            .unwrap();
+        assert_eq!(generate("imported", "again/mod.rs"), first);
+    }
+
+    #[test]
+    fn unknown_attribute_is_rejected() {
+        let syntax = parse_file(
+            "#[cfg(test)]\npub const A: ErrorCode = ErrorCode { code: 1, message: \"a\" };",
+        )
+        .unwrap();
+        let err = constants("errcode.rs", &syntax).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "errcode.rs:2: 无法导入 A 上的属性 #[cfg (test)]"
+        );
+    }
+    #[test]
+    fn yaml_keeps_order_and_quotes_messages() {
+        let row = |name: &str, code: i64, message: &str| Imported {
+            name: name.to_string(),
+            code,
+            message: message.to_string(),
+            ..Imported::default()
+        };
+        let rows = [
+            row("ORDER_NOT_FOUND", 10002, "yes"),
+            row("USER_NOT_FOUND", 10000, "a: b # c"),
+            row("USER_DISABLED", 10001, "123"),
+            row("USER_LOCKED", 10005, "第一行\n第二行"),
+            Imported {
+                messages: vec![
+                    (String::from("zh-CN"), String::from("过期")),
+                    (String::from("en"), String::from("'expired'")),
+                ],
+                deprecated: Some(String::new()),
+                ..row("TOKEN_EXPIRED", 10006, "过期")
+            },
+        ];
+        let yaml = serde_yml::to_string(&Value::Mapping(to_yaml(&rows, "user"))).unwrap();
+        assert_eq!(
+            yaml,
+            r#"_meta:
+  base: 10000
+  module: user
+order_not_found:
+  code: 10002
+  message: 'yes'
+user_not_found:
+  code: 10000
+  message: 'a: b # c'
+user_disabled: '123'
+user_locked:
+  code: 10005
+  message: |-
+    第一行
+    第二行
+token_expired:
+  message:
+    zh-CN: 过期
+    en: '''expired'''
+  deprecated: true
+"#
+        );
+
+        // 分组在常量名中已展开为前缀, 重新读取后常量名和错误码不变
+        let dir = scratch("import-yaml");
+        let path = format!("{}/user.yaml", dir);
+        std::fs::write(&path, &yaml).unwrap();
+        let document = crate::cmd::source::read(path.as_str()).unwrap().unwrap();
+        let entries: Vec<(&str, Option<i64>)> = document
+            .entries
+            .iter()
+            .map(|e| (e.name.as_str(), e.code))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("ORDER_NOT_FOUND", Some(10002)),
+                ("USER_NOT_FOUND", Some(10000)),
+                ("USER_DISABLED", None),
+                ("USER_LOCKED", Some(10005)),
+                ("TOKEN_EXPIRED", None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use crate::cmd::error::{Error, Result};
+use crate::cmd::plan::Plan;
+use serde_yml::{Mapping, Value};
+use std::collections::BTreeMap;
+
+///
+/// 错误码锁文件, 记录 名称 -> 错误码 的分配结果
+///
+/// 已记录的名称沿用原错误码, 新名称分配未被占用的错误码,
+/// 删除的名称移入 `reserved`, 其错误码不再分配给其他名称
+///
+/// Examples
+///```yaml
+/// codes:
+///   NOT_FOUND: 10000
+///   DISABLED: 10001
+/// reserved:
+///   EXPIRED: 10002
+///```
+///
+#[derive(Debug, Default)]
+pub(crate) struct Lock {
+    path: String,
+    codes: BTreeMap<String, i64>,
+    reserved: BTreeMap<String, i64>,
+}
+
+impl Lock {
+    /// 读取锁文件, 不存在时为空
+    pub(crate) fn load(plan: &Plan, path: &str) -> Result<Lock> {
+        let mut lock = Lock {
+            path: path.to_string(),
+            ..Lock::default()
+        };
+        let Some(content) = plan.read(path)? else {
+            return Ok(lock);
+        };
+        let value: Value = serde_yml::from_str(&content).map_err(|source| Error::Yaml {
+            path: path.to_string(),
+            source,
+        })?;
+        lock.codes = section(path, &value, "codes")?;
+        lock.reserved = section(path, &value, "reserved")?;
+        Ok(lock)
+    }
+
+    /// 已分配的错误码, 包括重新启用的保留名称
+    pub(crate) fn code(&self, name: &str) -> Option<i64> {
+        self.codes
+            .get(name)
+            .or_else(|| self.reserved.get(name))
+            .copied()
+    }
+
+    /// 错误码是否已被其他名称占用
+    pub(crate) fn is_taken(&self, code: i64, name: &str) -> bool {
+        self.owner(code).is_some_and(|owner| owner != name)
+    }
+
+    /// 占用错误码的名称
+    pub(crate) fn owner(&self, code: i64) -> Option<&str> {
+        self.codes
+            .iter()
+            .chain(self.reserved.iter())
+            .find(|(_, c)| **c == code)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// 用本次生成结果更新锁文件, 返回新删除的名称
+    pub(crate) fn update(&mut self, rows: &[(String, i64)]) -> Vec<(String, i64)> {
+        let mut removed = vec![];
+        for (name, code) in std::mem::take(&mut self.codes) {
+            if !rows.iter().any(|(n, _)| *n == name) {
+                removed.push((name.clone(), code));
+                self.reserved.insert(name, code);
+            }
+        }
+        for (name, code) in rows {
+            self.reserved.remove(name);
+            self.codes.insert(name.clone(), *code);
+        }
+        removed
+    }
+
+    pub(crate) fn write(&self, plan: &mut Plan) -> Result<()> {
+        let mut root = Mapping::new();
+        root.insert(Value::from("codes"), Value::Mapping(sorted(&self.codes)));
+        if !self.reserved.is_empty() {
+            root.insert(
+                Value::from("reserved"),
+                Value::Mapping(sorted(&self.reserved)),
+            );
+        }
+        let content =
+            serde_yml::to_string(&Value::Mapping(root)).map_err(|source| Error::Yaml {
+                path: self.path.clone(),
+                source,
+            })?;
+        plan.write(self.path.as_str(), content.trim_end())
+    }
+}
+
+fn section(path: &str, value: &Value, key: &str) -> Result<BTreeMap<String, i64>> {
+    let mut map = BTreeMap::new();
+    let Some(mapping) = value.get(key).and_then(|v| v.as_mapping()) else {
+        return Ok(map);
+    };
+    for (name, code) in mapping {
+        let (Some(name), Some(code)) = (name.as_str(), code.as_i64()) else {
+            return Err(Error::syntax(
+                path,
+                0,
+                format!("{} 中的条目应为 名称: 错误码", key),
+            ));
+        };
+        map.insert(name.to_string(), code);
+    }
+    Ok(map)
+}
+
+// 按错误码排序输出
+fn sorted(map: &BTreeMap<String, i64>) -> Mapping {
+    let mut entries: Vec<(&String, &i64)> = map.iter().collect();
+    entries.sort_by_key(|(name, code)| (**code, (*name).clone()));
+    let mut mapping = Mapping::new();
+    for (name, code) in entries {
+        mapping.insert(Value::from(name.as_str()), Value::from(*code));
+    }
+    mapping
+}
@@ -1,6 +1,17 @@
-pub mod r#gen;
+pub mod catalog;
 pub mod code;
+pub mod config;
+pub mod controller;
+pub mod ddl;
 pub mod r#enum;
+pub mod enum_body;
+pub mod error;
 pub mod file;
-pub mod controller;
-pub mod enum_body;
\ No newline at end of file
+pub mod r#gen;
+pub mod ident;
+pub mod import;
+pub mod lock;
+pub mod plan;
+pub mod report;
+pub mod source;
+pub mod stub;
@@ -0,0 +1,270 @@
+use crate::cmd::error::{Error, Result};
+use crate::cmd::file;
+use crate::cmd::report::{Action, Report};
+use similar::{ChangeTag, TextDiff};
+use std::io::IsTerminal;
+use std::path::Path;
+use std::str::FromStr;
+
+// 生成代码结束标记, 标记之后的手写代码在 merge 时保留
+pub(crate) const MARKER: &str = "// ---- hirust-gen: end of generated code ----";
+
+///
+/// 输出文件已存在时的处理策略
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnExists {
+    /// 跳过, 保留已有文件
+    Skip,
+    /// 覆盖
+    Overwrite,
+    /// 返回 [`Error::Exists`]
+    Error,
+    /// 重新生成标记之前的内容, 保留标记之后的手写代码
+    Merge,
+}
+
+impl FromStr for OnExists {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(OnExists::Skip),
+            "overwrite" => Ok(OnExists::Overwrite),
+            "error" => Ok(OnExists::Error),
+            "merge" => Ok(OnExists::Merge),
+            _ => Err(format!("unknown on-exists policy: {}", s)),
+        }
+    }
+}
+
+///
+/// `--check` 时 skip/error 的比较方式: 已有文件带标记时按 merge 比较, 不计标记之后的手写代码,
+/// 没有标记时报告文件已存在, 其他策略不变
+///
+pub(crate) fn check_policy(on_exists: OnExists, existing: Option<&str>) -> OnExists {
+    match on_exists {
+        OnExists::Skip | OnExists::Error => match existing {
+            Some(existing) if !existing.contains(MARKER) => OnExists::Error,
+            _ => OnExists::Merge,
+        },
+        on_exists => on_exists,
+    }
+}
+
+struct Change {
+    path: String,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+///
+/// 待写入的文件变更, 生成器先在内存中计算全部变更, 再统一写入或输出 diff
+///
+#[derive(Default)]
+pub(crate) struct Plan {
+    changes: Vec<Change>,
+}
+
+impl Plan {
+    /// 读取文件, 已计划写入的以计划内容为准
+    pub(crate) fn read(&self, path: &str) -> Result<Option<String>> {
+        let planned = self.changes.iter().find(|c| c.path == path);
+        if let Some(after) = planned.and_then(|c| c.after.as_ref()) {
+            return Ok(Some(after.clone()));
+        }
+        if Path::new(path).is_file() {
+            Ok(Some(file::read_file(path)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub(crate) fn exists(&self, path: &str) -> bool {
+        self.changes
+            .iter()
+            .any(|c| c.path == path && c.after.is_some())
+            || Path::new(path).exists()
+    }
+
+    /// 计划写入文件, 末尾追加换行
+    pub(crate) fn write(&mut self, path: &str, content: &str) -> Result<()> {
+        let after = format!("{}\n", content);
+        match self.changes.iter_mut().find(|c| c.path == path) {
+            Some(change) => change.after = Some(after),
+            None => {
+                let before = if Path::new(path).is_file() {
+                    Some(file::read_file(path)?)
+                } else {
+                    None
+                };
+                self.changes.push(Change {
+                    path: path.to_string(),
+                    before,
+                    after: Some(after),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// 按 `on_exists` 策略写入文件
+    pub(crate) fn write_with(
+        &mut self,
+        path: &str,
+        content: &str,
+        on_exists: OnExists,
+    ) -> Result<()> {
+        if !self.exists(path) {
+            return self.write(path, content);
+        }
+        match on_exists {
+            OnExists::Skip => {
+                eprintln!("{}已经存在", path);
+                self.skip(path);
+                Ok(())
+            }
+            OnExists::Overwrite => self.write(path, content),
+            OnExists::Error => Err(Error::Exists {
+                path: path.to_string(),
+            }),
+            OnExists::Merge => {
+                let existing = self.read(path)?.unwrap_or_default();
+                let merged = merge_preserved(path, existing.as_str(), content)?;
+                self.write(path, merged.as_str())
+            }
+        }
+    }
+
+    /// 记录跳过的文件
+    pub(crate) fn skip(&mut self, path: &str) {
+        if !self.changes.iter().any(|c| c.path == path) {
+            self.changes.push(Change {
+                path: path.to_string(),
+                before: None,
+                after: None,
+            });
+        }
+    }
+
+    pub(crate) fn report(&self) -> Report {
+        let mut report = Report::default();
+        for change in &self.changes {
+            match (&change.before, &change.after) {
+                (_, None) => report.push(change.path.as_str(), Action::Skipped),
+                (None, Some(_)) => report.push(change.path.as_str(), Action::Created),
+                (Some(before), Some(after)) if before != after => {
+                    report.push(change.path.as_str(), Action::Modified)
+                }
+                _ => {}
+            }
+        }
+        report
+    }
+
+    /// 写入全部变更
+    pub(crate) fn apply(&self) -> Result<()> {
+        for change in &self.changes {
+            let Some(after) = &change.after else {
+                continue;
+            };
+            if change.before.as_ref() == Some(after) {
+                continue;
+            }
+            if let Some(parent) = Path::new(change.path.as_str()).parent() {
+                file::create_dir(parent.display().to_string().as_str())?;
+            }
+            std::fs::write(change.path.as_str(), after)
+                .map_err(|e| Error::io(change.path.as_str(), e))?;
+        }
+        Ok(())
+    }
+
+    /// 写入全部变更, `dry_run` 时只输出 diff, `check` 时有变更则输出 diff 并返回错误
+    pub(crate) fn commit(&self, dry_run: bool, check: bool) -> Result<Report> {
+        let report = self.report();
+        if check {
+            let paths: Vec<String> = report
+                .files
+                .iter()
+                .filter(|f| f.action != Action::Skipped)
+                .map(|f| f.path.clone())
+                .collect();
+            if !paths.is_empty() {
+                print!("{}", self.diff());
+                return Err(Error::Stale { paths });
+            }
+        } else if dry_run {
+            print!("{}", self.diff());
+        } else {
+            self.apply()?;
+        }
+        Ok(report)
+    }
+
+    /// 全部变更的 unified diff, 输出到终端时着色
+    pub(crate) fn diff(&self) -> String {
+        let color = std::io::stdout().is_terminal();
+        let mut out = String::new();
+        for change in &self.changes {
+            let Some(after) = &change.after else {
+                continue;
+            };
+            let before = change.before.clone().unwrap_or_default();
+            if before == *after {
+                continue;
+            }
+            let old_header = match change.before {
+                Some(_) => format!("a/{}", change.path),
+                None => String::from("/dev/null"),
+            };
+            let new_header = format!("b/{}", change.path);
+            let diff = TextDiff::from_lines(before.as_str(), after.as_str());
+            push_line(&mut out, format!("--- {}\n", old_header), "\x1b[1m", color);
+            push_line(&mut out, format!("+++ {}\n", new_header), "\x1b[1m", color);
+            for hunk in diff.unified_diff().context_radius(3).iter_hunks() {
+                push_line(&mut out, format!("{}\n", hunk.header()), "\x1b[36m", color);
+                for change in hunk.iter_changes() {
+                    let (sign, style) = match change.tag() {
+                        ChangeTag::Delete => ("-", "\x1b[31m"),
+                        ChangeTag::Insert => ("+", "\x1b[32m"),
+                        ChangeTag::Equal => (" ", ""),
+                    };
+                    let mut line = format!("{}{}", sign, change.value());
+                    if change.missing_newline() {
+                        line.push_str("\n\\ No newline at end of file\n");
+                    }
+                    push_line(&mut out, line, style, color);
+                }
+            }
+        }
+        out
+    }
+}
+
+fn push_line(out: &mut String, line: String, style: &str, color: bool) {
+    if color && !style.is_empty() {
+        out.push_str(style);
+        out.push_str(line.trim_end_matches('\n'));
+        out.push_str("\x1b[0m\n");
+    } else {
+        out.push_str(line.as_str());
+    }
+}
+
+// 用新生成的内容替换标记之前的部分, 保留标记之后的手写代码
+fn merge_preserved(path: &str, existing: &str, generated: &str) -> Result<String> {
+    let Some(pos) = existing.find(MARKER) else {
+        return Err(Error::syntax(
+            path,
+            0,
+            format!("缺少标记注释 `{}`, 无法合并", MARKER),
+        ));
+    };
+    let tail = existing[pos + MARKER.len()..].trim_end();
+    let head = match generated.find(MARKER) {
+        Some(pos) => generated[..pos].to_string(),
+        None => format!("{}\n\n", generated.trim_end()),
+    };
+    Ok(format!("{}{}{}", head, MARKER, tail))
+}
@@ -0,0 +1,54 @@
+///
+/// 文件处理结果
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Created,
+    Modified,
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileReport {
+    pub path: String,
+    pub action: Action,
+}
+
+///
+/// 缺少翻译的错误码
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingTranslation {
+    pub name: String,
+    pub locale: String,
+}
+
+///
+/// 生成报告, 按处理顺序记录生成器涉及的文件
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    pub files: Vec<FileReport>,
+    /// `gen code` 中缺少翻译的错误码
+    pub missing_translations: Vec<MissingTranslation>,
+    /// `gen code` 中与上次生成结果相比删除的常量名
+    pub removed: Vec<String>,
+}
+
+impl Report {
+    pub(crate) fn push(&mut self, path: &str, action: Action) {
+        self.files.push(FileReport {
+            path: path.to_string(),
+            action,
+        });
+    }
+
+    #[allow(dead_code)]
+    pub fn paths(&self, action: Action) -> Vec<&str> {
+        self.files
+            .iter()
+            .filter(|f| f.action == action)
+            .map(|f| f.path.as_str())
+            .collect()
+    }
+}
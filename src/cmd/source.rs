@@ -0,0 +1,519 @@
+use crate::cmd::error::{Error, Result};
+use crate::cmd::{file, ident};
+use serde_yml::{Mapping, Value};
+use std::path::Path;
+
+///
+/// 错误码源文件格式, 按扩展名识别
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Format {
+    Yaml,
+    Json,
+    Toml,
+}
+
+impl Format {
+    pub(crate) fn from_path(path: &str) -> Option<Format> {
+        match Path::new(path).extension().and_then(|s| s.to_str()) {
+            Some("yaml" | "yml") => Some(Format::Yaml),
+            Some("json") => Some(Format::Json),
+            Some("toml") => Some(Format::Toml),
+            _ => None,
+        }
+    }
+
+    /// 解析为与 YAML 相同的结构, 保留文档中的键顺序
+    pub(crate) fn parse(self, path: &str, content: &str) -> Result<Value> {
+        match self {
+            Format::Yaml => serde_yml::from_str(content).map_err(|source| Error::Yaml {
+                path: path.to_string(),
+                source,
+            }),
+            Format::Json => serde_json::from_str(content)
+                .map_err(|e| Error::syntax(path, e.line(), format!("解析JSON失败: {}", e))),
+            Format::Toml => toml::from_str(content).map_err(|e| {
+                let line = e
+                    .span()
+                    .map(|span| content[..span.start].lines().count().max(1))
+                    .unwrap_or(0);
+                Error::syntax(path, line, format!("解析TOML失败: {}", e.message()))
+            }),
+        }
+    }
+
+    // 键在源文件中的写法
+    fn key_patterns(self, key: &str) -> Vec<String> {
+        match self {
+            Format::Yaml => vec![
+                format!("{}:", key),
+                format!("\"{}\":", key),
+                format!("'{}':", key),
+            ],
+            Format::Json => vec![format!("\"{}\"", key)],
+            Format::Toml => vec![
+                format!("{} ", key),
+                format!("{}=", key),
+                format!("\"{}\"", key),
+                format!("[{}]", key),
+            ],
+        }
+    }
+}
+
+///
+/// 源文件中的一个错误码定义
+///
+pub(crate) struct Entry {
+    /// 完整键路径, 分组之间以 . 连接
+    pub key: String,
+    pub name: String,
+    pub code: Option<i64>,
+    pub message: String,
+    /// 按 locale 区分的 message, 按文档顺序
+    pub messages: Vec<(String, String)>,
+    pub http_status: Option<u16>,
+    /// warn 或 error
+    pub level: Option<String>,
+    pub retryable: Option<bool>,
+    /// `--style=enum` 时变体的字段 名称 -> 类型, 按文档顺序
+    pub fields: Vec<(String, String)>,
+    /// 弃用说明, `deprecated: true` 时为空字符串
+    pub deprecated: Option<String>,
+    pub line: usize,
+}
+
+///
+/// 源文件头部 `_meta` 声明的错误码范围
+///
+/// Examples
+///```yaml
+/// _meta: {base: 10000, max: 10999, module: user, retired: [10003]}
+///```
+///
+#[derive(Debug, Default)]
+pub(crate) struct Meta {
+    pub base: Option<i64>,
+    pub max: Option<i64>,
+    pub module: Option<String>,
+    /// 已停用的错误码, 不能再分配
+    pub retired: Vec<i64>,
+    /// `_meta` 所在行, 0 表示未声明
+    pub line: usize,
+}
+
+///
+/// 一个源文件的解析结果
+///
+pub(crate) struct Document {
+    pub meta: Meta,
+    pub entries: Vec<Entry>,
+}
+
+///
+/// 读取错误码源文件, 不支持的文件类型返回 None
+///
+pub(crate) fn read(path: &str) -> Result<Option<Document>> {
+    let Some(format) = Format::from_path(path) else {
+        return Ok(None);
+    };
+    let content = file::read_file(path)?;
+    let source = Source {
+        path,
+        content: content.as_str(),
+        format,
+    };
+    let mut document = Document {
+        meta: Meta::default(),
+        entries: vec![],
+    };
+    match format.parse(path, content.as_str())? {
+        Value::Null => {}
+        Value::Mapping(mut mapping) => {
+            if let Some(meta) = mapping.shift_remove(META) {
+                document.meta = source.meta(&meta)?;
+            }
+            source.collect(&mapping, "", 0, &mut document.entries)?
+        }
+        _ => return Err(Error::syntax(path, 1, "顶层必须是映射")),
+    }
+    Ok(Some(document))
+}
+
+// 保留键, 声明本文件的错误码范围
+const META: &str = "_meta";
+
+struct Source<'a> {
+    path: &'a str,
+    content: &'a str,
+    format: Format,
+}
+
+impl Source<'_> {
+    fn meta(&self, value: &Value) -> Result<Meta> {
+        let line = self.locate(0, META);
+        let syntax = |message: &str| Error::syntax(self.path, line, message);
+        let mapping = value
+            .as_mapping()
+            .ok_or_else(|| syntax("_meta 必须是映射"))?;
+        let mut meta = Meta {
+            line,
+            ..Meta::default()
+        };
+        for (key, value) in mapping {
+            match key.as_str() {
+                Some("base") => {
+                    meta.base = Some(
+                        value
+                            .as_i64()
+                            .ok_or_else(|| syntax("_meta.base 必须是整数"))?,
+                    )
+                }
+                Some("max") => {
+                    meta.max = Some(
+                        value
+                            .as_i64()
+                            .ok_or_else(|| syntax("_meta.max 必须是整数"))?,
+                    )
+                }
+                Some("module") => {
+                    meta.module = Some(
+                        value
+                            .as_str()
+                            .ok_or_else(|| syntax("_meta.module 必须是字符串"))?
+                            .to_string(),
+                    )
+                }
+                Some("retired") => {
+                    meta.retired = value
+                        .as_sequence()
+                        .and_then(|codes| codes.iter().map(|c| c.as_i64()).collect())
+                        .ok_or_else(|| syntax("_meta.retired 必须是整数列表"))?
+                }
+                _ => return Err(syntax(format!("_meta 中未知的键 {:?}", key).as_str())),
+            }
+        }
+        if let (Some(base), Some(max)) = (meta.base, meta.max)
+            && max < base
+        {
+            return Err(syntax("_meta.max 不能小于 base"));
+        }
+        Ok(meta)
+    }
+
+    // 按文档顺序收集错误码定义, 值为空或字符串(message 简写)或映射,
+    // 不含任何错误码定义键的映射作为分组, 常量名加分组前缀
+    fn collect(
+        &self,
+        mapping: &Mapping,
+        prefix: &str,
+        from_line: usize,
+        entries: &mut Vec<Entry>,
+    ) -> Result<()> {
+        let path = self.path;
+        for (key, value) in mapping {
+            let key = match key {
+                Value::String(key) => key.clone(),
+                Value::Number(key) => key.to_string(),
+                _ => {
+                    return Err(Error::syntax(
+                        path,
+                        from_line,
+                        format!("{}下存在非字符串键", prefix),
+                    ));
+                }
+            };
+            let line = self.locate(from_line, key.as_str());
+            let full_key = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            let mut entry = Entry {
+                key: full_key.clone(),
+                name: ident::constant(full_key.replace('.', "_").as_str())
+                    .map_err(|message| Error::syntax(path, line, message))?,
+                code: None,
+                message: key.clone() + "错误",
+                messages: vec![],
+                http_status: None,
+                level: None,
+                retryable: None,
+                fields: vec![],
+                deprecated: None,
+                line,
+            };
+            match value {
+                Value::Null => {}
+                Value::String(message) => entry.message = message.clone(),
+                Value::Mapping(group) if is_group(group) => {
+                    self.collect(group, full_key.as_str(), line, entries)?;
+                    continue;
+                }
+                Value::Mapping(mapping) => {
+                    if let Some(unknown) = mapping
+                        .keys()
+                        .find(|k| !k.as_str().is_some_and(|k| ENTRY_KEYS.contains(&k)))
+                    {
+                        return Err(Error::syntax(
+                            path,
+                            line,
+                            format!("{} 中未知的键 {:?}", full_key, unknown),
+                        ));
+                    }
+                    if let Some(value) = mapping.get("message") {
+                        self.message(value, full_key.as_str(), line, &mut entry)?;
+                    }
+                    if let Some(value) = mapping.get("code") {
+                        entry.code = Some(value.as_i64().ok_or_else(|| {
+                            Error::syntax(path, line, format!("{}.code 必须是整数", full_key))
+                        })?);
+                    }
+                    if let Some(value) = mapping.get("http_status") {
+                        entry.http_status = Some(
+                            value
+                                .as_u64()
+                                .filter(|status| (100..=599).contains(status))
+                                .ok_or_else(|| {
+                                    Error::syntax(
+                                        path,
+                                        line,
+                                        format!("{}.http_status 必须是 100-599 的整数", full_key),
+                                    )
+                                })? as u16,
+                        );
+                    }
+                    if let Some(value) = mapping.get("level") {
+                        entry.level = Some(
+                            value
+                                .as_str()
+                                .filter(|level| matches!(*level, "warn" | "error"))
+                                .ok_or_else(|| {
+                                    Error::syntax(
+                                        path,
+                                        line,
+                                        format!("{}.level 必须是 warn 或 error", full_key),
+                                    )
+                                })?
+                                .to_string(),
+                        );
+                    }
+                    if let Some(value) = mapping.get("deprecated") {
+                        entry.deprecated = match value {
+                            Value::String(reason) => Some(reason.clone()),
+                            Value::Bool(true) => Some(String::new()),
+                            Value::Bool(false) | Value::Null => None,
+                            _ => {
+                                return Err(Error::syntax(
+                                    path,
+                                    line,
+                                    format!("{}.deprecated 必须是字符串或布尔值", full_key),
+                                ));
+                            }
+                        };
+                    }
+                    if let Some(value) = mapping.get("fields") {
+                        self.fields(value, full_key.as_str(), line, &mut entry)?;
+                    }
+                    if let Some(value) = mapping.get("retryable") {
+                        entry.retryable = Some(value.as_bool().ok_or_else(|| {
+                            Error::syntax(
+                                path,
+                                line,
+                                format!("{}.retryable 必须是布尔值", full_key),
+                            )
+                        })?);
+                    }
+                }
+                _ => {
+                    return Err(Error::syntax(
+                        path,
+                        line,
+                        format!("{} 的值必须为空、字符串或映射", full_key),
+                    ));
+                }
+            }
+            entries.push(entry);
+        }
+        Ok(())
+    }
+
+    // message 为字符串或 locale -> 文本 的映射
+    fn message(&self, value: &Value, key: &str, line: usize, entry: &mut Entry) -> Result<()> {
+        match value {
+            Value::String(message) => entry.message = message.clone(),
+            Value::Mapping(mapping) if !mapping.is_empty() => {
+                for (locale, message) in mapping {
+                    let (Some(locale), Some(message)) = (locale.as_str(), message.as_str()) else {
+                        return Err(Error::syntax(
+                            self.path,
+                            line,
+                            format!("{}.message 的条目应为 locale: 文本", key),
+                        ));
+                    };
+                    entry
+                        .messages
+                        .push((locale.to_string(), message.to_string()));
+                }
+            }
+            _ => {
+                return Err(Error::syntax(
+                    self.path,
+                    line,
+                    format!("{}.message 必须是字符串或 locale 映射", key),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // fields 为 字段名 -> Rust 类型 的映射
+    fn fields(&self, value: &Value, key: &str, line: usize, entry: &mut Entry) -> Result<()> {
+        let syntax = || {
+            Error::syntax(
+                self.path,
+                line,
+                format!("{}.fields 的条目应为 字段名: 类型", key),
+            )
+        };
+        let mapping = value.as_mapping().ok_or_else(syntax)?;
+        for (name, ty) in mapping {
+            let (Some(name), Some(ty)) = (name.as_str(), ty.as_str()) else {
+                return Err(syntax());
+            };
+            let name =
+                ident::snake(name).map_err(|message| Error::syntax(self.path, line, message))?;
+            // 字段会在 Display 的格式化字符串中引用, 不能是原始标识符
+            if name.starts_with("r#") {
+                return Err(Error::syntax(
+                    self.path,
+                    line,
+                    format!(
+                        "{}.fields 中的字段名 {} 是 Rust 关键字",
+                        key,
+                        ident::unraw(&name)
+                    ),
+                ));
+            }
+            entry.fields.push((name, ty.to_string()));
+        }
+        Ok(())
+    }
+
+    // 查找键所在行号, 从父级所在行之后开始查找, 找不到时返回父级行号
+    fn locate(&self, from_line: usize, key: &str) -> usize {
+        let patterns = self.format.key_patterns(key);
+        self.content
+            .lines()
+            .enumerate()
+            .skip(from_line)
+            .find(|(_, row)| {
+                let row = row.trim_start();
+                patterns.iter().any(|p| {
+                    row.starts_with(p.as_str())
+                        || (self.format == Format::Toml
+                            && row.starts_with('[')
+                            && row.trim_end().ends_with(format!(".{}]", key).as_str()))
+                })
+            })
+            .map(|(i, _)| i + 1)
+            .unwrap_or(from_line.max(1))
+    }
+}
+
+// 错误码定义中可使用的键
+const ENTRY_KEYS: [&str; 7] = [
+    "message",
+    "code",
+    "http_status",
+    "level",
+    "retryable",
+    "fields",
+    "deprecated",
+];
+
+// 不含任何错误码定义键的非空映射作为分组
+fn is_group(mapping: &Mapping) -> bool {
+    !mapping.is_empty()
+        && !mapping
+            .keys()
+            .any(|k| k.as_str().is_some_and(|k| ENTRY_KEYS.contains(&k)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 写入临时文件后读取
+    fn read_str(name: &str, content: &str) -> Result<Document> {
+        let path = std::env::temp_dir().join(format!("hirust_gen_source_{}", name));
+        std::fs::write(&path, content).unwrap();
+        let document = read(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        document.map(|d| d.unwrap())
+    }
+
+    fn names(document: &Document) -> Vec<(&str, &str)> {
+        document
+            .entries
+            .iter()
+            .map(|e| (e.name.as_str(), e.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn group_with_string_leaves() {
+        let document = read_str(
+            "string_leaves.yaml",
+            "order:\n  created: 已创建\n  paid: 已支付\n",
+        )
+        .unwrap();
+        assert_eq!(
+            names(&document),
+            [("ORDER_CREATED", "已创建"), ("ORDER_PAID", "已支付")]
+        );
+        assert_eq!(document.entries[1].line, 3);
+    }
+
+    #[test]
+    fn group_with_mixed_leaves() {
+        let document = read_str(
+            "mixed_leaves.yaml",
+            "user:\n  missing:\n  locked: 已锁定\n  banned:\n    code: 20\n    message: 已封禁\n  auth:\n    expired: 已过期\n",
+        )
+        .unwrap();
+        assert_eq!(
+            names(&document),
+            [
+                ("USER_MISSING", "missing错误"),
+                ("USER_LOCKED", "已锁定"),
+                ("USER_BANNED", "已封禁"),
+                ("USER_AUTH_EXPIRED", "已过期"),
+            ]
+        );
+        assert_eq!(document.entries[2].code, Some(20));
+    }
+
+    #[test]
+    fn entry_with_only_fields() {
+        let document = read_str(
+            "only_fields.yaml",
+            "with_fields:\n  fields:\n    user_id: i64\n",
+        )
+        .unwrap();
+        assert_eq!(names(&document), [("WITH_FIELDS", "with_fields错误")]);
+        assert_eq!(
+            document.entries[0].fields,
+            [(String::from("user_id"), String::from("i64"))]
+        );
+    }
+
+    #[test]
+    fn unknown_entry_key() {
+        let result = read_str(
+            "unknown_key.yaml",
+            "paid:\n  message: 已支付\n  mesage: x\n",
+        );
+        assert!(matches!(result, Err(Error::Syntax { line: 1, .. })));
+    }
+}
@@ -0,0 +1,141 @@
+use crate::cmd::config::Config;
+use crate::cmd::error::{Error, Result};
+use crate::cmd::file;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use minijinja::Environment;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+pub(crate) const CODE: &str = "code.stub";
+pub(crate) const CODE_ENUM: &str = "code_enum.stub";
+pub(crate) const CODE_MD: &str = "code_md.stub";
+pub(crate) const CODE_TS: &str = "code_ts.stub";
+pub(crate) const ENUM: &str = "enum.stub";
+pub(crate) const ENUM_NATIVE: &str = "enum_native.stub";
+pub(crate) const CONTROLLER: &str = "controller.stub";
+
+// 默认发布目录
+const PUBLISH_DIR: &str = "./stubs";
+
+// 内置模板, 编译期打包进二进制
+const BUILTIN: [(&str, &str); 7] = [
+    (CODE, include_str!("../stubs/code.stub")),
+    (CODE_ENUM, include_str!("../stubs/code_enum.stub")),
+    (CODE_MD, include_str!("../stubs/code_md.stub")),
+    (CODE_TS, include_str!("../stubs/code_ts.stub")),
+    (ENUM, include_str!("../stubs/enum.stub")),
+    (ENUM_NATIVE, include_str!("../stubs/enum_native.stub")),
+    (CONTROLLER, include_str!("../stubs/controller.stub")),
+];
+
+// cargo run gen stubs publish
+// cargo run gen stubs publish --out="./stubs" --force
+#[allow(dead_code)]
+pub(crate) fn command() -> Command {
+    Command::new("stubs")
+        .about("stubs.")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("publish")
+                .about("Copy the built-in stubs into the project.")
+                .arg(
+                    Arg::new("out")
+                        .short('o')
+                        .long("out")
+                        .action(ArgAction::Set)
+                        .help("Output Directory"),
+                )
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .action(ArgAction::SetTrue)
+                        .help("Overwrite existing stubs"),
+                ),
+        )
+}
+
+#[allow(dead_code)]
+pub(crate) fn execute(arg_matches: &ArgMatches) -> Result<()> {
+    if let Some(publish_matches) = arg_matches.subcommand_matches("publish") {
+        let mut out_dir = crate::cmd::r#gen::out_file(publish_matches);
+        if out_dir.is_empty() {
+            out_dir = Config::load()?
+                .templates
+                .unwrap_or_else(|| PUBLISH_DIR.to_string());
+        }
+        publish(out_dir.as_str(), publish_matches.get_flag("force"))?;
+    }
+    Ok(())
+}
+
+fn publish(out_dir: &str, force: bool) -> Result<()> {
+    file::create_dir(out_dir)?;
+    for (name, source) in BUILTIN {
+        let stub_file = format!("{}/{}", out_dir, name);
+        if Path::new(stub_file.as_str()).exists() && !force {
+            println!("{}已经存在", stub_file);
+            continue;
+        }
+        fs::write(stub_file.as_str(), source).map_err(|e| Error::io(stub_file.as_str(), e))?;
+    }
+    Ok(())
+}
+
+///
+/// 模板注册表, code/enum/controller 生成器共用
+///
+/// 查找顺序: `--templates` 目录 > 配置文件 `templates` 目录 > 内置模板
+///
+pub(crate) struct Registry {
+    sources: BTreeMap<&'static str, String>,
+}
+
+impl Registry {
+    #[allow(dead_code)]
+    pub(crate) fn new() -> Self {
+        let sources = BUILTIN
+            .iter()
+            .map(|(name, source)| (*name, source.to_string()))
+            .collect();
+        Registry { sources }
+    }
+
+    /// 按查找顺序加载模板, `templates` 为空时读取配置文件
+    #[allow(dead_code)]
+    pub(crate) fn load(templates: &str) -> Result<Self> {
+        let mut registry = Registry::new();
+        let templates_dir = if templates.is_empty() {
+            Config::load()?.templates.unwrap_or_default()
+        } else {
+            templates.to_string()
+        };
+        if templates_dir.is_empty() {
+            return Ok(registry);
+        }
+        for (name, source) in registry.sources.iter_mut() {
+            let stub_file = format!("{}/{}", templates_dir, name);
+            if Path::new(stub_file.as_str()).is_file() {
+                *source = file::read_file(stub_file.as_str())?;
+            }
+        }
+        Ok(registry)
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn render<S: Serialize>(&self, name: &str, ctx: S) -> Result<String> {
+        let template_err = |source| Error::Template {
+            name: name.to_string(),
+            source,
+        };
+        let source = self.sources.get(name).map(|s| s.as_str()).unwrap_or("");
+        let mut env = Environment::new();
+        // Rust 字符串字面量, 转义引号和反斜杠
+        env.add_filter("rust_str", |s: String| format!("{:?}", s));
+        env.add_template(name, source).map_err(template_err)?;
+        let template = env.get_template(name).map_err(template_err)?;
+        template.render(ctx).map_err(template_err)
+    }
+}
@@ -1,4 +1,12 @@
 pub mod cmd;
 
+pub use crate::cmd::catalog::CodeFormat;
+pub use crate::cmd::code::{CodeGen, CodeStyle};
+pub use crate::cmd::controller::ControllerGen;
+pub use crate::cmd::r#enum::{EnumDb, EnumGen, EnumSerde, EnumStyle};
+pub use crate::cmd::enum_body::Enum;
+pub use crate::cmd::error::{Error, Result};
 pub use crate::cmd::r#gen::*;
-pub use crate::cmd::enum_body::Enum;
\ No newline at end of file
+pub use crate::cmd::import::CodeImport;
+pub use crate::cmd::plan::OnExists;
+pub use crate::cmd::report::{Action, FileReport, MissingTranslation, Report};